@@ -1,3 +1,6 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+
 use color_eyre::Report;
 use color_eyre::Result;
 use serenity::{
@@ -5,18 +8,31 @@ use serenity::{
     model::prelude::{
         Activity,
         component::ComponentType,
-        GuildId, interaction::{Interaction, InteractionResponseType}, Ready,
+        ChannelId, GuildId, interaction::{Interaction, InteractionResponseType}, Member, Message,
+        MessageId, Ready, UserId,
     },
     prelude::{Context, EventHandler},
 };
+use serenity::builder::CreateEmbed;
 use serenity::futures::stream::BoxStream;
 use serenity::futures::StreamExt;
 use serenity::model::application::interaction::message_component::MessageComponentInteraction;
+use serenity::model::application::interaction::modal::ModalSubmitInteraction;
+use serenity::model::event::MessageUpdateEvent;
 use serenity::model::prelude::interaction::application_command::ApplicationCommandInteraction;
+use tokio::sync::OnceCell;
 use tracing::{info, warn};
 
+use crate::api::schema::report;
+use crate::api::schema::response::Response;
 use crate::{api::schema::circle::Circle, commands, managers::circle::CircleManager};
+use crate::managers::audit::AuditManager;
+use crate::managers::automod::AutomodManager;
+use crate::managers::division::DivisionManager;
+use crate::managers::moderation::ModerationManager;
+use crate::managers::raid::RaidManager;
 use crate::managers::report::ReportManager;
+use crate::managers::verification::VerificationManager;
 
 use super::super::managers::firestore::FSManager;
 use super::super::settings::Settings;
@@ -26,12 +42,22 @@ pub struct Bot {
     pub firestore_manager: FSManager,
     pub circle_manager: CircleManager,
     pub report_manager: ReportManager,
+    pub moderation_manager: ModerationManager,
+    pub automod_manager: AutomodManager,
+    pub audit_manager: AuditManager,
+    pub raid_manager: RaidManager,
+    pub verification_manager: VerificationManager,
+    pub division_manager: DivisionManager,
+    tasks_started: AtomicBool,
+    /// The bot's own user, known once it is ready
+    user_id: OnceCell<UserId>,
 }
 
 #[async_trait]
 impl EventHandler for Bot {
     async fn ready(&self, ctx: Context, ready: Ready) {
         println!("{} is connected!", ready.user.name);
+        let _ = self.user_id.set(ready.user.id);
         ctx.set_activity(Activity::watching(&self.settings.activity.description))
             .await;
 
@@ -40,13 +66,23 @@ impl EventHandler for Bot {
 
         let commands = GuildId::set_application_commands(&guild_id, &ctx.http, |commands| {
             commands
-                // .create_application_command(|cmd| commands::admin::register(cmd))
+                .create_application_command(|cmd| commands::admin::register(cmd))
                 .create_application_command(|cmd| commands::circle::register(cmd))
                 .create_application_command(|cmd| {
                     cmd.name("recache").description("Recache the bot")
                 })
                 .create_application_command(|cmd| commands::ping::register(cmd))
                 .create_application_command(|cmd| commands::report::register(cmd))
+                .create_application_command(|cmd| commands::report::register_slash(cmd))
+                .create_application_command(|cmd| commands::strike::register(cmd))
+                .create_application_command(|cmd| commands::mute::register(cmd))
+                .create_application_command(|cmd| commands::mute::register_unmute(cmd))
+                .create_application_command(|cmd| commands::kick::register(cmd))
+                .create_application_command(|cmd| commands::ban::register(cmd))
+                .create_application_command(|cmd| commands::response::register(cmd))
+                .create_application_command(|cmd| commands::case::register(cmd))
+                .create_application_command(|cmd| commands::lockdown::register(cmd))
+                .create_application_command(|cmd| commands::rolepanel::register(cmd))
         })
         .await
         .unwrap_or_else(|why| {
@@ -59,6 +95,65 @@ impl EventHandler for Bot {
         }
 
         self.recache_ctx(&ctx).await.unwrap();
+        self.start_tasks(&ctx).await;
+    }
+
+    async fn message(&self, ctx: Context, new_message: Message) {
+        self.audit_manager.remember(&new_message).await;
+        if let Err(why) = self.automod_manager.check(&ctx, self, &new_message).await {
+            warn!("Unable to run automod on {}: {:?}", new_message.id, why);
+        }
+    }
+
+    async fn message_update(&self, ctx: Context, new_data: MessageUpdateEvent) {
+        if let Err(why) = self.audit_manager.message_edited(&ctx, &new_data).await {
+            warn!("Unable to log the edit of {}: {:?}", new_data.id, why);
+        }
+    }
+
+    async fn message_delete(
+        &self,
+        ctx: Context,
+        channel_id: ChannelId,
+        deleted_message_id: MessageId,
+        _guild_id: Option<GuildId>,
+    ) {
+        if let Err(why) = self
+            .audit_manager
+            .message_deleted(&ctx, channel_id, deleted_message_id)
+            .await
+        {
+            warn!("Unable to log the deletion of {}: {:?}", deleted_message_id, why);
+        }
+    }
+
+    async fn message_delete_bulk(
+        &self,
+        ctx: Context,
+        channel_id: ChannelId,
+        multiple_deleted_messages_ids: Vec<MessageId>,
+        _guild_id: Option<GuildId>,
+    ) {
+        if let Err(why) = self
+            .audit_manager
+            .messages_deleted(&ctx, channel_id, &multiple_deleted_messages_ids)
+            .await
+        {
+            warn!("Unable to log the bulk deletion in {}: {:?}", channel_id, why);
+        }
+    }
+
+    async fn guild_member_addition(&self, ctx: Context, new_member: Member) {
+        if let Err(why) = self
+            .moderation_manager
+            .reapply_mute(&ctx, self, new_member.user.id)
+            .await
+        {
+            warn!("Unable to check the mute of {}: {:?}", new_member.user.id, why);
+        }
+        if let Err(why) = self.raid_manager.record_join(&ctx, self, &new_member).await {
+            warn!("Unable to check for a raid: {:?}", why);
+        }
     }
 
     async fn interaction_create(&self, ctx: Context, interaction: Interaction) {
@@ -73,6 +168,11 @@ impl EventHandler for Bot {
                     warn!("Error handling component: {:?}", why);
                 }
             }
+            Interaction::ModalSubmit(modal) => {
+                if let Err(why) = self.handle_modal(&ctx, modal).await {
+                    warn!("Error handling modal: {:?}", why);
+                }
+            }
             _ => {}
         }
     }
@@ -83,19 +183,115 @@ impl Bot {
         let settings = Settings::new();
         let firestore_manager = FSManager::new().await;
         let circle_manager = CircleManager::new(&settings);
-        let report_manager = ReportManager::default();
+        let report_manager = ReportManager::new(&settings);
+        let moderation_manager = ModerationManager::new(&settings);
+        let automod_manager = AutomodManager::new(&settings);
+        let audit_manager = AuditManager::new(&settings);
+        let raid_manager = RaidManager::new(&settings);
+        let verification_manager = VerificationManager::new(&settings);
+        let division_manager = DivisionManager::new(&settings);
         Self {
             settings,
             firestore_manager,
             circle_manager,
             report_manager,
+            moderation_manager,
+            automod_manager,
+            audit_manager,
+            raid_manager,
+            verification_manager,
+            division_manager,
+            tasks_started: AtomicBool::new(false),
+            user_id: OnceCell::new(),
+        }
+    }
+
+    /// Get the id of the bot's own user
+    /// # Errors
+    /// * If the bot has not been ready yet
+    pub fn user_id(&self) -> Result<UserId> {
+        self.user_id
+            .get()
+            .copied()
+            .ok_or(eyre::eyre!("The bot is not ready yet"))
+    }
+
+    /// Spawn the background jobs of the bot, `ready` can fire again on reconnects
+    /// so this only does anything the first time it is called
+    async fn start_tasks(&self, ctx: &Context) {
+        if self.tasks_started.swap(true, Ordering::SeqCst) {
+            return;
+        }
+
+        match self.raid_manager.load_lockdown(self).await {
+            Ok(true) => info!("The server is still locked down"),
+            Ok(false) => {}
+            Err(why) => warn!("Unable to load the lockdown: {:?}", why),
+        }
+        match self.moderation_manager.reschedule_mutes(ctx, self).await {
+            Ok(count) => info!("Rescheduled {} mutes", count),
+            Err(why) => warn!("Unable to reschedule mutes: {:?}", why),
+        }
+        match self.moderation_manager.reschedule_bans(ctx, self).await {
+            Ok(count) => info!("Rescheduled {} temporary bans", count),
+            Err(why) => warn!("Unable to reschedule temporary bans: {:?}", why),
         }
+        if let Err(why) = self.verification_manager.post_panel(ctx, self).await {
+            warn!("Unable to post the verification panel: {:?}", why);
+        }
+
+        let report_manager = self.report_manager.clone();
+        let sweep_ctx = ctx.clone();
+        let period = Duration::from_secs(self.settings.reports.sweep_interval_secs.max(1));
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(period);
+            loop {
+                interval.tick().await;
+                if let Err(why) = report_manager.sweep_pending(&sweep_ctx).await {
+                    warn!("Unable to sweep pending reports: {:?}", why);
+                }
+            }
+        });
+
+        let firestore_manager = self.firestore_manager.clone();
+        let period = Duration::from_secs(self.settings.moderation.decay_interval_secs.max(1));
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(period);
+            loop {
+                interval.tick().await;
+                match firestore_manager.member_decay_all().await {
+                    Ok(0) => {}
+                    Ok(count) => info!("Forgave decayed strikes for {} members", count),
+                    Err(why) => warn!("Unable to decay strikes: {:?}", why),
+                }
+            }
+        });
     }
 
     pub async fn recache_ctx(&self, ctx: &Context) -> Result<String> {
         let db = self.firestore_manager.client.fluent();
-        let circles: BoxStream<Circle> = db.select().from("circle").obj().stream_query().await?;
+        let circles: BoxStream<Circle> = db
+            .clone()
+            .select()
+            .from("circle")
+            .obj()
+            .stream_query()
+            .await?;
         let vectors: Vec<Circle> = circles.collect().await;
+        let reports: BoxStream<report::Report> = db
+            .clone()
+            .select()
+            .from("report")
+            .obj()
+            .stream_query()
+            .await?;
+        let reports: Vec<report::Report> = reports
+            .filter(|r| std::future::ready(r.status.is_open()))
+            .collect()
+            .await;
+        let responses: BoxStream<Response> =
+            db.select().from("response").obj().stream_query().await?;
+        let responses: Vec<Response> = responses.collect().await;
 
         let mut data = ctx.data.write().await;
         let circles = data
@@ -106,6 +302,23 @@ impl Bot {
             circles.insert(circle.id.clone(), circle);
         }
 
+        let open_reports = data
+            .get_mut::<report::Report>()
+            .ok_or(eyre::eyre!("No report data"))?;
+        info!("Recaching {} open reports", reports.len());
+        for report in reports {
+            open_reports.insert(report.id.clone(), report);
+        }
+
+        let cached_responses = data
+            .get_mut::<Response>()
+            .ok_or(eyre::eyre!("No response data"))?;
+        info!("Recaching {} responses", responses.len());
+        cached_responses.clear();
+        for response in responses {
+            cached_responses.insert(response.type_field.clone(), response);
+        }
+
         Ok("Recached".to_string())
     }
 
@@ -113,11 +326,39 @@ impl Bot {
         info!("Command: {:?}", cmd.data.name);
 
         let content = match cmd.data.name.as_str() {
-            // "admin" => commands::admin::run(&cmd.data.options, self).await,
             "circle" => commands::circle::run(&cmd.data.options, ctx, self).await,
             "recache" => self.recache_ctx(ctx).await,
             "beep" => Ok(commands::ping::run()),
-            "report" => commands::report::run(ctx, &cmd, self).await,
+            "strike" => commands::strike::run(ctx, cmd, self).await,
+            "mute" => commands::mute::run(ctx, cmd, self).await,
+            "unmute" => commands::mute::run_unmute(ctx, cmd, self).await,
+            "kick" => commands::kick::run(ctx, cmd, self).await,
+            "ban" => commands::ban::run(ctx, cmd, self).await,
+            "lockdown" => commands::lockdown::run(&cmd.data.options, ctx, cmd, self).await,
+            "rolepanel" => commands::rolepanel::run(ctx, cmd, self).await,
+            "Anonymous Report" => {
+                // The category prompt is the response, only errors still need one
+                return match commands::report::run(ctx, cmd, self).await {
+                    Ok(()) => Ok(()),
+                    Err(why) => self.respond_embed(ctx, cmd, Err(why)).await,
+                };
+            }
+            "report" => {
+                let res = commands::report::run_slash(&cmd.data.options, cmd, self).await;
+                return self.respond_embed(ctx, cmd, res).await;
+            }
+            "admin" => {
+                let res = commands::admin::run(&cmd.data.options, ctx, cmd, self).await;
+                return self.respond_embed(ctx, cmd, res).await;
+            }
+            "case" => {
+                let res = commands::case::run(&cmd.data.options, ctx, cmd, self).await;
+                return self.respond_embed(ctx, cmd, res).await;
+            }
+            "response" => {
+                let res = commands::response::run(&cmd.data.options, ctx, cmd, self).await;
+                return self.respond_embed(ctx, cmd, res).await;
+            }
             _ => Err(Report::msg("Unknown command")),
         };
 
@@ -153,6 +394,37 @@ impl Bot {
         }
         Ok(())
     }
+
+    /// Respond to a command with an embed, or the error that happened while building it
+    async fn respond_embed(
+        &self,
+        ctx: &Context,
+        cmd: &ApplicationCommandInteraction,
+        embed: Result<CreateEmbed>,
+    ) -> Result<()> {
+        let res = match embed {
+            Ok(embed) => {
+                cmd.create_interaction_response(&ctx.http, |r| {
+                    r.kind(InteractionResponseType::ChannelMessageWithSource)
+                        .interaction_response_data(|d| d.set_embed(embed).ephemeral(true))
+                })
+                .await
+            }
+            Err(why) => {
+                warn!("Cannot respond to command: {:?}", why);
+                cmd.create_interaction_response(&ctx.http, |r| {
+                    r.kind(InteractionResponseType::ChannelMessageWithSource)
+                        .interaction_response_data(|d| d.content(why).ephemeral(true))
+                })
+                .await
+            }
+        };
+        if let Err(why) = res {
+            warn!("Cannot respond to command: {:?}", why);
+        }
+        Ok(())
+    }
+
     async fn handle_button(&self, ctx: &Context, msg: &MessageComponentInteraction) -> Result<()> {
         if msg.data.component_type != ComponentType::Button {
             return Ok(());
@@ -176,6 +448,232 @@ impl Bot {
                     warn!("Cannot respond to command button: {:?}", why);
                 }
             }
+        } else if msg.data.custom_id.starts_with("report/") {
+            let res = self.report_manager.handle_button(ctx, msg, self).await;
+            let res = match res {
+                // The reporter has been asked for details instead
+                Ok(None) => return Ok(()),
+                Ok(Some(res)) => {
+                    msg.create_interaction_response(&ctx.http, |r| {
+                        r.kind(InteractionResponseType::UpdateMessage)
+                            .interaction_response_data(|d| {
+                                d.content(res).set_embeds(Vec::new()).components(|c| c)
+                            })
+                    })
+                    .await
+                }
+                Err(why) => {
+                    warn!("Cannot handle report button: {:?}", why);
+                    msg.create_interaction_response(&ctx.http, |r| {
+                        r.kind(InteractionResponseType::ChannelMessageWithSource)
+                            .interaction_response_data(|d| d.content(why).ephemeral(true))
+                    })
+                    .await
+                }
+            };
+            if let Err(why) = res {
+                warn!("Cannot respond to report button: {:?}", why);
+            }
+        } else if msg.data.custom_id.starts_with("triage/") {
+            // Striking the author can take longer than Discord waits for a response
+            let res = msg
+                .create_interaction_response(&ctx.http, |r| {
+                    r.kind(InteractionResponseType::DeferredUpdateMessage)
+                })
+                .await;
+            if let Err(why) = res {
+                warn!("Cannot respond to triage button: {:?}", why);
+                return Ok(());
+            }
+            let res = self.report_manager.handle_triage(ctx, msg, self).await;
+            let res = match res {
+                Ok((embed, rows)) => msg
+                    .edit_original_interaction_response(&ctx.http, |r| {
+                        r.set_embed(embed).components(|c| c.set_action_rows(rows))
+                    })
+                    .await
+                    .map(|_| ()),
+                Err(why) => {
+                    warn!("Cannot handle triage button: {:?}", why);
+                    msg.create_followup_message(&ctx.http, |f| f.content(why).ephemeral(true))
+                        .await
+                        .map(|_| ())
+                }
+            };
+            if let Err(why) = res {
+                warn!("Cannot respond to triage button: {:?}", why);
+            }
+        } else if msg.data.custom_id.starts_with("appeal/") {
+            let res = self
+                .moderation_manager
+                .handle_appeal_button(ctx, msg, self)
+                .await;
+            let res = match res {
+                // The member has been sent a modal to write their appeal
+                Ok(None) => return Ok(()),
+                Ok(Some(embed)) => {
+                    msg.create_interaction_response(&ctx.http, |r| {
+                        r.kind(InteractionResponseType::UpdateMessage)
+                            .interaction_response_data(|d| d.set_embed(embed).components(|c| c))
+                    })
+                    .await
+                }
+                Err(why) => {
+                    warn!("Cannot handle appeal button: {:?}", why);
+                    msg.create_interaction_response(&ctx.http, |r| {
+                        r.kind(InteractionResponseType::ChannelMessageWithSource)
+                            .interaction_response_data(|d| d.content(why).ephemeral(true))
+                    })
+                    .await
+                }
+            };
+            if let Err(why) = res {
+                warn!("Cannot respond to appeal button: {:?}", why);
+            }
+        } else if msg.data.custom_id.starts_with("division/") {
+            let content = self
+                .division_manager
+                .handle_button(ctx, msg)
+                .await
+                .unwrap_or_else(|why| {
+                    warn!("Cannot handle division button: {:?}", why);
+                    why.to_string()
+                });
+            let res = msg
+                .create_interaction_response(&ctx.http, |r| {
+                    r.kind(InteractionResponseType::ChannelMessageWithSource)
+                        .interaction_response_data(|d| d.content(content).ephemeral(true))
+                })
+                .await;
+            if let Err(why) = res {
+                warn!("Cannot respond to division button: {:?}", why);
+            }
+        } else if msg.data.custom_id.starts_with("verify/") {
+            // On success the member has been sent a modal
+            let res = self
+                .verification_manager
+                .handle_button(ctx, msg, self)
+                .await;
+            if let Err(why) = res {
+                warn!("Cannot handle verify button: {:?}", why);
+                let res = msg
+                    .create_interaction_response(&ctx.http, |r| {
+                        r.kind(InteractionResponseType::ChannelMessageWithSource)
+                            .interaction_response_data(|d| d.content(why).ephemeral(true))
+                    })
+                    .await;
+                if let Err(why) = res {
+                    warn!("Cannot respond to verify button: {:?}", why);
+                }
+            }
+        } else if msg.data.custom_id.starts_with("followup/") {
+            // On success the member has been sent a modal
+            let res = self
+                .report_manager
+                .handle_followup_button(ctx, msg, self)
+                .await;
+            if let Err(why) = res {
+                warn!("Cannot handle follow up button: {:?}", why);
+                let res = msg
+                    .create_interaction_response(&ctx.http, |r| {
+                        r.kind(InteractionResponseType::ChannelMessageWithSource)
+                            .interaction_response_data(|d| d.content(why).ephemeral(true))
+                    })
+                    .await;
+                if let Err(why) = res {
+                    warn!("Cannot respond to follow up button: {:?}", why);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    async fn handle_modal(&self, ctx: &Context, modal: &ModalSubmitInteraction) -> Result<()> {
+        if modal.data.custom_id.starts_with("report/") {
+            let res = self.report_manager.handle_modal(ctx, modal, self).await;
+            let res = match res {
+                Ok(res) => {
+                    modal
+                        .create_interaction_response(&ctx.http, |r| {
+                            r.kind(InteractionResponseType::UpdateMessage)
+                                .interaction_response_data(|d| {
+                                    d.content(res).set_embeds(Vec::new()).components(|c| c)
+                                })
+                        })
+                        .await
+                }
+                Err(why) => {
+                    warn!("Cannot handle report modal: {:?}", why);
+                    modal
+                        .create_interaction_response(&ctx.http, |r| {
+                            r.kind(InteractionResponseType::ChannelMessageWithSource)
+                                .interaction_response_data(|d| d.content(why).ephemeral(true))
+                        })
+                        .await
+                }
+            };
+            if let Err(why) = res {
+                warn!("Cannot respond to report modal: {:?}", why);
+            }
+        } else if modal.data.custom_id.starts_with("appeal/") {
+            let res = self
+                .moderation_manager
+                .handle_appeal_modal(ctx, modal, self)
+                .await;
+            let content = res.unwrap_or_else(|why| {
+                warn!("Cannot handle appeal modal: {:?}", why);
+                why.to_string()
+            });
+            let res = modal
+                .create_interaction_response(&ctx.http, |r| {
+                    r.kind(InteractionResponseType::ChannelMessageWithSource)
+                        .interaction_response_data(|d| d.content(content).ephemeral(true))
+                })
+                .await;
+            if let Err(why) = res {
+                warn!("Cannot respond to appeal modal: {:?}", why);
+            }
+        } else if modal.data.custom_id.starts_with("verify/") {
+            let res = self
+                .verification_manager
+                .handle_modal(ctx, modal, self)
+                .await;
+            let (content, row) = res.unwrap_or_else(|why| {
+                warn!("Cannot handle verify modal: {:?}", why);
+                (why.to_string(), None)
+            });
+            let res = modal
+                .create_interaction_response(&ctx.http, |r| {
+                    r.kind(InteractionResponseType::ChannelMessageWithSource)
+                        .interaction_response_data(|d| {
+                            if let Some(row) = row {
+                                d.components(|c| c.add_action_row(row));
+                            }
+                            d.content(content).ephemeral(true)
+                        })
+                })
+                .await;
+            if let Err(why) = res {
+                warn!("Cannot respond to verify modal: {:?}", why);
+            }
+        } else if modal.data.custom_id.starts_with("followup/") {
+            let res = self
+                .report_manager
+                .handle_followup_modal(ctx, modal, self)
+                .await;
+            let content = res.unwrap_or_else(|why| {
+                warn!("Cannot handle follow up modal: {:?}", why);
+                why.to_string()
+            });
+            let res = modal
+                .create_interaction_response(&ctx.http, |r| {
+                    r.kind(InteractionResponseType::ChannelMessageWithSource)
+                        .interaction_response_data(|d| d.content(content).ephemeral(true))
+                })
+                .await;
+            if let Err(why) = res {
+                warn!("Cannot respond to follow up modal: {:?}", why);
+            }
         }
         Ok(())
     }
@@ -0,0 +1,122 @@
+use std::fmt::Display;
+
+use chrono::{DateTime, Utc};
+use serde_derive::Deserialize;
+use serde_derive::Serialize;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CaseAction {
+    Strike,
+    Mute,
+    Kick,
+    Ban,
+    ReportResolved,
+    ReportDismissed,
+}
+
+impl Display for CaseAction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Strike => write!(f, "Strike"),
+            Self::Mute => write!(f, "Mute"),
+            Self::Kick => write!(f, "Kick"),
+            Self::Ban => write!(f, "Ban"),
+            Self::ReportResolved => write!(f, "Report Resolved"),
+            Self::ReportDismissed => write!(f, "Report Dismissed"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Case {
+    /// The case number as a string, used as the document id
+    #[serde(rename = "_id")]
+    pub id: String,
+    pub number: i64,
+    pub action: CaseAction,
+    /// The discord id of the member the action was taken against
+    pub target: String,
+    /// The discord id of the staff member who took the action
+    pub moderator: String,
+    pub reason: String,
+    /// Extra context such as the strike count or mute duration
+    #[serde(default)]
+    pub details: Option<String>,
+    /// The report that led to this case
+    #[serde(default)]
+    pub report: Option<String>,
+    /// The id of the case's message in the mod channel
+    #[serde(default)]
+    pub log_message: Option<String>,
+    #[serde(default)]
+    pub appeal: Option<Appeal>,
+    #[serde(with = "firestore::serialize_as_timestamp")]
+    pub created_on: DateTime<Utc>,
+}
+
+impl Case {
+    /// A case that has not been numbered yet, it is numbered once it is saved
+    pub fn new(action: CaseAction, target: &str, moderator: &str, reason: &str) -> Self {
+        Self {
+            id: String::new(),
+            number: 0,
+            action,
+            target: target.to_string(),
+            moderator: moderator.to_string(),
+            reason: reason.to_string(),
+            details: None,
+            report: None,
+            log_message: None,
+            appeal: None,
+            created_on: Utc::now(),
+        }
+    }
+}
+
+impl CaseAction {
+    /// Whether the member is told about the action and can appeal it
+    pub fn is_punishment(&self) -> bool {
+        matches!(self, Self::Strike | Self::Mute | Self::Kick | Self::Ban)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum AppealStatus {
+    Pending,
+    Accepted,
+    Denied,
+}
+
+impl Display for AppealStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Pending => write!(f, "Pending"),
+            Self::Accepted => write!(f, "Accepted"),
+            Self::Denied => write!(f, "Denied"),
+        }
+    }
+}
+
+/// A member's request to have the action of a case reversed
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Appeal {
+    pub message: String,
+    pub status: AppealStatus,
+    #[serde(with = "firestore::serialize_as_timestamp")]
+    pub submitted_on: DateTime<Utc>,
+    #[serde(default)]
+    pub handled_by: Option<String>,
+    #[serde(default, with = "firestore::serialize_as_optional_timestamp")]
+    pub handled_on: Option<DateTime<Utc>>,
+}
+
+/// Keeps track of the last case number that was given out
+#[derive(Default, Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CaseCounter {
+    #[serde(rename = "_id")]
+    pub id: String,
+    pub last: i64,
+}
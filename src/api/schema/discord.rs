@@ -0,0 +1,18 @@
+use serde_derive::Deserialize;
+use serde_derive::Serialize;
+
+/// The link between a discord account and a member's ACM profile
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Discord {
+    /// The discord id of the member
+    #[serde(rename = "_id")]
+    pub id: String,
+    #[serde(default)]
+    pub name: Option<String>,
+    #[serde(default)]
+    pub net_id: Option<String>,
+    /// Verified during a lockdown, the member role is given once it is lifted
+    #[serde(default)]
+    pub pending_role: bool,
+}
@@ -0,0 +1,16 @@
+use chrono::{DateTime, Utc};
+use serde_derive::Deserialize;
+use serde_derive::Serialize;
+
+/// A server lockdown, saved so it can still be lifted after a restart
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Lockdown {
+    /// The id of the locked down server
+    #[serde(rename = "_id")]
+    pub id: String,
+    /// The verification level to go back to once the lockdown is lifted, as Discord numbers it
+    pub previous_level: i64,
+    #[serde(with = "firestore::serialize_as_timestamp")]
+    pub locked_on: DateTime<Utc>,
+}
@@ -1,5 +1,6 @@
 use std::collections::HashMap;
 
+use chrono::{DateTime, Duration, Utc};
 use firestore::FirestoreTimestamp;
 use serde_derive::Deserialize;
 use serde_derive::Serialize;
@@ -12,9 +13,62 @@ pub struct Member {
     pub id: String,
     pub strikes: i64,
     pub last_strike: FirestoreTimestamp,
+    /// When strikes were last forgiven, decay counts from here or `last_strike`, whichever is later
+    #[serde(default)]
+    pub last_decay: Option<FirestoreTimestamp>,
+    #[serde(default)]
+    pub kicks: i64,
+    #[serde(default)]
+    pub bans: i64,
+    /// When a temporary ban is lifted, `None` when the member is not temporarily banned
+    #[serde(default, with = "firestore::serialize_as_optional_timestamp")]
+    pub banned_until: Option<DateTime<Utc>>,
     pub preferences: Preferences,
 }
 
+impl Member {
+    /// A member without any moderation history
+    pub fn new(id: &str) -> Self {
+        Self {
+            id: id.to_string(),
+            strikes: 0,
+            last_strike: FirestoreTimestamp(DateTime::<Utc>::from(std::time::UNIX_EPOCH)),
+            last_decay: None,
+            kicks: 0,
+            bans: 0,
+            banned_until: None,
+            preferences: Preferences::default(),
+        }
+    }
+
+    /// Forgive one strike for every `every` that passed without a new strike
+    /// # Arguments
+    /// * `every` - How long a member has to go without a strike to lose one
+    /// * `now` - The time to decay up to
+    /// # Returns
+    /// Whether any strikes were forgiven
+    pub fn decay(&mut self, every: Duration, now: DateTime<Utc>) -> bool {
+        if self.strikes <= 0 || every <= Duration::zero() {
+            return false;
+        }
+        let since = match &self.last_decay {
+            Some(last_decay) => last_decay.0.max(self.last_strike.0),
+            None => self.last_strike.0,
+        };
+        let periods = (now - since).num_seconds() / every.num_seconds();
+        if periods <= 0 {
+            return false;
+        }
+
+        let forgiven = periods.min(self.strikes);
+        self.strikes -= forgiven;
+        self.last_decay = Some(FirestoreTimestamp(
+            since + Duration::seconds(every.num_seconds() * forgiven),
+        ));
+        true
+    }
+}
+
 #[derive(Default, Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct Preferences {
@@ -1,4 +1,9 @@
+pub mod case;
 pub mod circle;
 pub mod coper;
+pub mod discord;
+pub mod lockdown;
 pub mod member;
+pub mod mute;
+pub mod report;
 pub mod response;
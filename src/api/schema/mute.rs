@@ -0,0 +1,24 @@
+use chrono::{DateTime, Utc};
+use serde_derive::Deserialize;
+use serde_derive::Serialize;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Mute {
+    /// The discord id of the muted member
+    #[serde(rename = "_id")]
+    pub id: String,
+    pub moderator: String,
+    pub reason: String,
+    #[serde(with = "firestore::serialize_as_timestamp")]
+    pub muted_on: DateTime<Utc>,
+    /// When the mute is lifted, `None` lasts until staff lift it
+    #[serde(default, with = "firestore::serialize_as_optional_timestamp")]
+    pub expires_on: Option<DateTime<Utc>>,
+}
+
+impl Mute {
+    pub fn is_expired(&self, now: DateTime<Utc>) -> bool {
+        matches!(self.expires_on, Some(expires_on) if expires_on <= now)
+    }
+}
@@ -0,0 +1,134 @@
+use std::collections::HashMap;
+use std::fmt::Display;
+
+use chrono::{DateTime, Utc};
+use serde_derive::Deserialize;
+use serde_derive::Serialize;
+use serenity::prelude::TypeMapKey;
+use strum_macros::{AsRefStr, EnumIter, EnumString};
+
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, EnumIter, EnumString, AsRefStr,
+)]
+pub enum ReportCategories {
+    Offensive,
+    SpamOrAds,
+    IllegalOrNSFW,
+    Uncomfortable,
+    Other,
+}
+
+impl ReportCategories {
+    /// Whether the reporter should be asked to describe what happened
+    pub fn needs_details(&self) -> bool {
+        matches!(self, Self::Uncomfortable | Self::Other)
+    }
+}
+
+impl Display for ReportCategories {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Offensive => write!(f, "Offensive"),
+            Self::SpamOrAds => write!(f, "Spam/Ads"),
+            Self::IllegalOrNSFW => write!(f, "Illegal or NSFW"),
+            Self::Uncomfortable => write!(f, "Uncomfortable"),
+            Self::Other => write!(f, "Other"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum ReportStatus {
+    Open,
+    Acknowledged,
+    Resolved,
+    Dismissed,
+}
+
+impl ReportStatus {
+    /// Whether moderators still need to act on a report with this status
+    pub fn is_open(&self) -> bool {
+        matches!(self, Self::Open | Self::Acknowledged)
+    }
+}
+
+impl Display for ReportStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Open => write!(f, "Open"),
+            Self::Acknowledged => write!(f, "Acknowledged"),
+            Self::Resolved => write!(f, "Resolved"),
+            Self::Dismissed => write!(f, "Dismissed"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Report {
+    #[serde(rename = "_id")]
+    pub id: String,
+    /// The category of the first report
+    pub category: ReportCategories,
+    /// The category of every report of the message
+    #[serde(default)]
+    pub categories: Vec<ReportCategories>,
+    /// Salted hashes of the people who reported the message
+    #[serde(default)]
+    pub reporters: Vec<String>,
+    pub message_link: String,
+    pub message_id: String,
+    pub channel_id: String,
+    pub reported_user: String,
+    pub content: String,
+    /// Everything else about the message at the time it was first reported
+    #[serde(default)]
+    pub evidence: Option<Evidence>,
+    #[serde(default)]
+    pub details: Option<String>,
+    #[serde(default)]
+    pub ongoing: Option<String>,
+    #[serde(with = "firestore::serialize_as_timestamp")]
+    pub created_on: DateTime<Utc>,
+    pub status: ReportStatus,
+    pub handled_by: Option<String>,
+    #[serde(default, with = "firestore::serialize_as_optional_timestamp")]
+    pub handled_on: Option<DateTime<Utc>>,
+    /// The id of the report card in the mod channel
+    #[serde(default)]
+    pub card_message: Option<String>,
+    #[serde(default)]
+    pub escalated: bool,
+    /// The id of the follow up thread on the report card
+    #[serde(default)]
+    pub thread: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Evidence {
+    pub author_tag: String,
+    #[serde(with = "firestore::serialize_as_timestamp")]
+    pub sent_on: DateTime<Utc>,
+    pub embeds: Vec<EmbedSnapshot>,
+    pub attachments: Vec<AttachmentSnapshot>,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EmbedSnapshot {
+    pub title: Option<String>,
+    pub description: Option<String>,
+    pub url: Option<String>,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AttachmentSnapshot {
+    pub filename: String,
+    pub url: String,
+}
+
+impl TypeMapKey for Report {
+    type Value = HashMap<String, Self>;
+}
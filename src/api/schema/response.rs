@@ -2,8 +2,10 @@ use std::{collections::HashMap, fmt::Display};
 
 use serde::{Deserialize, Serialize};
 use serenity::prelude::TypeMapKey;
+use strum_macros::{EnumIter, EnumString};
 
-#[derive(Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, EnumIter, EnumString)]
+#[strum(serialize_all = "lowercase")]
 pub enum ResponsesType {
     Strike,
     Kick,
@@ -32,6 +34,22 @@ pub struct Response {
     pub message: String,
 }
 
+impl Response {
+    /// Fill in the `{user}`, `{reason}` and `{duration}` placeholders of the message,
+    /// messages without a `{reason}` placeholder get the reason appended
+    pub fn render(&self, user: &str, reason: &str, duration: Option<&str>) -> String {
+        let message = self
+            .message
+            .replace("{user}", user)
+            .replace("{duration}", duration.unwrap_or("indefinitely"));
+        if message.contains("{reason}") {
+            message.replace("{reason}", reason)
+        } else {
+            format!("{message}\n**Reason:** {reason}")
+        }
+    }
+}
+
 impl TypeMapKey for Response {
     type Value = HashMap<String, Self>;
 }
@@ -1,12 +1,24 @@
+use chrono::Utc;
 use color_eyre::Result;
-use eyre::{Context, ContextCompat};
-use serenity::builder::CreateApplicationCommand;
+use serenity::builder::{CreateApplicationCommand, CreateEmbed};
+use serenity::client::Context;
 use serenity::model::prelude::command::CommandOptionType;
 use serenity::model::prelude::interaction::application_command::{
-    CommandDataOption, CommandDataOptionValue,
+    ApplicationCommandInteraction, CommandDataOption, CommandDataOptionValue,
 };
+use serenity::model::prelude::{GuildId, RoleId};
+use serenity::utils::Color;
 
+use super::parse_option;
 use crate::api::bot::Bot;
+use crate::api::schema::circle::Circle;
+use crate::util::roles::has_role;
+use crate::util::text::truncate;
+
+/// How many of the member's latest cases are shown
+const RECENT_CASES: usize = 5;
+/// Longest reason shown per recent case, keeps the field under 1024 characters
+const RECENT_REASON_LENGTH: usize = 150;
 
 pub fn register(cmd: &mut CreateApplicationCommand) -> &mut CreateApplicationCommand {
     cmd.name("admin")
@@ -20,32 +32,138 @@ pub fn register(cmd: &mut CreateApplicationCommand) -> &mut CreateApplicationCom
         })
 }
 
-pub async fn run(options: &[CommandDataOption], bot: &Bot) -> Result<String> {
-    let data = options
-        .get(0)
-        .expect("No options provided")
-        .resolved
+pub async fn run(
+    options: &[CommandDataOption],
+    ctx: &Context,
+    cmd: &ApplicationCommandInteraction,
+    bot: &Bot,
+) -> Result<CreateEmbed> {
+    if !has_role(cmd.member.as_ref(), &bot.settings.roles.staff) {
+        return Err(eyre::eyre!("Only staff can look up members"));
+    }
+
+    let user = match parse_option(options, "user")? {
+        CommandDataOptionValue::User(user, _member) => user,
+        _ => Err(eyre::eyre!("No user provided"))?,
+    };
+    let user_id = user.id.to_string();
+
+    let fs = &bot.firestore_manager;
+    let profile = fs.discord_get(&user_id).await?;
+    let record = fs.member_get(&user_id).await?;
+    let mute = fs.mute_get(&user_id).await?;
+    let coper = fs.coper_get(&user_id).await?;
+    let cases = fs.case_history(&user_id).await?;
+    // Members who left the server can still be looked up
+    let member = GuildId(bot.settings.guild).member(ctx, user.id).await.ok();
+
+    let mut embed = CreateEmbed::default();
+    embed
+        .title(format!("Profile of {}", user.tag()))
+        .thumbnail(user.face())
+        .color(Color::BLUE);
+
+    let linked = match profile {
+        Some(profile) => match (profile.name, profile.net_id) {
+            (Some(name), Some(net_id)) => format!("{name} ({net_id})"),
+            (Some(name), None) => name,
+            (None, Some(net_id)) => net_id,
+            (None, None) => "Linked without a name".to_string(),
+        },
+        None => "Not linked".to_string(),
+    };
+    embed.field("**Profile**", linked, true);
+
+    let joined = member
         .as_ref()
-        .context("No user provided")?;
+        .and_then(|member| member.joined_at)
+        .map_or("Not in the server".to_string(), |joined| {
+            format!("<t:{}:D>", joined.unix_timestamp())
+        });
+    embed.field("**Joined**", joined, true);
+    embed.field(
+        "**Points**",
+        coper.map_or(0, |coper| coper.score).to_string(),
+        true,
+    );
 
-    let user_id = match data {
-        CommandDataOptionValue::User(user, _member) => user.id,
-        _ => panic!("No user provided"),
+    let strikes = match &record {
+        Some(record) if record.strikes > 0 => format!(
+            "{} (last <t:{}:R>)",
+            record.strikes,
+            record.last_strike.0.timestamp()
+        ),
+        _ => "0".to_string(),
     };
+    embed.field("**Strikes**", strikes, true);
 
-    let name: Option<String> = bot
-        .firestore_manager
-        .client
-        .fluent()
-        .select()
-        .by_id_in("discord")
-        .obj()
-        .one(user_id.to_string())
-        .await
-        .context("Failed to get user")?;
-
-    match name {
-        Some(name) => Ok(format!("{} is {}", user_id, name)),
-        None => Ok(format!("{} is not in the database", user_id)),
+    let now = Utc::now();
+    let mut status = Vec::new();
+    match mute {
+        Some(mute) if !mute.is_expired(now) => status.push(match mute.expires_on {
+            Some(expires_on) => format!("Muted until <t:{}:f>", expires_on.timestamp()),
+            None => "Muted until lifted".to_string(),
+        }),
+        _ => {}
     }
+    if let Some(banned_until) = record.as_ref().and_then(|record| record.banned_until) {
+        status.push(format!("Banned until <t:{}:f>", banned_until.timestamp()));
+    }
+    if let Some(record) = &record {
+        if record.kicks > 0 || record.bans > 0 {
+            status.push(format!("{} kicks, {} bans", record.kicks, record.bans));
+        }
+    }
+    if status.is_empty() {
+        status.push("In good standing".to_string());
+    }
+    embed.field("**Status**", status.join("\n"), true);
+
+    let circles = {
+        let data = ctx.data.read().await;
+        let roles: &[RoleId] = member.as_ref().map_or(&[], |member| &member.roles);
+        data.get::<Circle>()
+            .map(|circles| {
+                circles
+                    .values()
+                    .filter(|circle| {
+                        matches!(circle.id.parse::<u64>(), Ok(id) if roles.contains(&RoleId(id)))
+                    })
+                    .map(|circle| format!("{} {}", circle.emoji, circle.name))
+                    .collect::<Vec<_>>()
+            })
+            .unwrap_or_default()
+    };
+    embed.field(
+        "**Circles**",
+        match circles.is_empty() {
+            true => "None".to_string(),
+            false => circles.join(", "),
+        },
+        false,
+    );
+
+    let recent = cases
+        .iter()
+        .rev()
+        .take(RECENT_CASES)
+        .map(|case| {
+            format!(
+                "#{} {} - {}",
+                case.number,
+                case.action,
+                truncate(&case.reason, RECENT_REASON_LENGTH)
+            )
+        })
+        .collect::<Vec<_>>();
+    embed.field(
+        format!("**Recent Cases** ({} total)", cases.len()),
+        match recent.is_empty() {
+            true => "None".to_string(),
+            false => recent.join("\n"),
+        },
+        false,
+    );
+
+    Ok(embed)
 }
@@ -0,0 +1,103 @@
+use color_eyre::Result;
+use serenity::builder::CreateApplicationCommand;
+use serenity::model::prelude::command::CommandOptionType;
+use serenity::model::prelude::interaction::application_command::{
+    ApplicationCommandInteraction, CommandDataOptionValue,
+};
+use serenity::prelude::Context;
+
+use super::parse_option;
+use crate::api::bot::Bot;
+use crate::util::roles::has_role;
+use crate::util::time::{format_duration, parse_duration};
+
+pub fn register(cmd: &mut CreateApplicationCommand) -> &mut CreateApplicationCommand {
+    cmd.name("ban")
+        .description("Ban a member from the server")
+        .create_option(|option| {
+            option
+                .name("user")
+                .description("The member to ban")
+                .kind(CommandOptionType::User)
+                .required(true)
+        })
+        .create_option(|option| {
+            option
+                .name("reason")
+                .description("Why the member is being banned")
+                .kind(CommandOptionType::String)
+                .required(true)
+        })
+        .create_option(|option| {
+            option
+                .name("delete_days")
+                .description("How many days of their messages to delete")
+                .kind(CommandOptionType::Integer)
+                .min_int_value(0)
+                .max_int_value(7)
+                .required(false)
+        })
+        .create_option(|option| {
+            option
+                .name("duration")
+                .description("How long the ban lasts, e.g. 12h or 7d, leave empty to ban for good")
+                .kind(CommandOptionType::String)
+                .required(false)
+        })
+}
+
+pub async fn run(ctx: &Context, cmd: &ApplicationCommandInteraction, bot: &Bot) -> Result<String> {
+    if !has_role(cmd.member.as_ref(), &bot.settings.roles.staff) {
+        return Err(eyre::eyre!("Only staff can ban members"));
+    }
+
+    let options = &cmd.data.options;
+    let user = match parse_option(options, "user")? {
+        CommandDataOptionValue::User(user, _member) => user,
+        _ => Err(eyre::eyre!("No user provided"))?,
+    };
+    let reason = match parse_option(options, "reason")? {
+        CommandDataOptionValue::String(reason) => reason,
+        _ => Err(eyre::eyre!("No reason provided"))?,
+    };
+    let delete_days = match parse_option(options, "delete_days") {
+        Ok(CommandDataOptionValue::Integer(days)) => u8::try_from((*days).clamp(0, 7))?,
+        _ => 0,
+    };
+    let duration = match parse_option(options, "duration") {
+        Ok(CommandDataOptionValue::String(duration)) => {
+            Some(parse_duration(duration).ok_or(eyre::eyre!(
+                "Invalid duration {}, use e.g. 12h or 7d, up to a year",
+                duration
+            ))?)
+        }
+        _ => None,
+    };
+    if user.bot {
+        return Err(eyre::eyre!("Bots cannot be banned"));
+    }
+
+    bot.moderation_manager
+        .check_hierarchy(ctx, user.id, cmd.user.id)
+        .await?;
+    bot.moderation_manager
+        .ban(
+            ctx,
+            bot,
+            user.id,
+            cmd.user.id,
+            reason,
+            delete_days,
+            duration,
+        )
+        .await?;
+
+    Ok(match duration {
+        Some(duration) => format!(
+            "{} has been banned for {}",
+            user.tag(),
+            format_duration(duration)
+        ),
+        None => format!("{} has been banned", user.tag()),
+    })
+}
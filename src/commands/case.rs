@@ -0,0 +1,150 @@
+use color_eyre::Result;
+use serenity::builder::{CreateApplicationCommand, CreateEmbed};
+use serenity::client::Context;
+use serenity::model::prelude::command::CommandOptionType;
+use serenity::model::prelude::interaction::application_command::{
+    ApplicationCommandInteraction, CommandDataOption, CommandDataOptionValue,
+};
+use serenity::utils::Color;
+
+use super::parse_option;
+use crate::api::bot::Bot;
+use crate::managers::moderation::case_embed;
+use crate::util::roles::has_role;
+use crate::util::text::truncate;
+
+/// The most cases shown by `/case history`, the newest ones are kept
+const HISTORY_LIMIT: usize = 20;
+/// Longest reason shown per case in `/case history`, keeps the embed under 6000 characters
+const HISTORY_REASON_LENGTH: usize = 200;
+
+pub fn register(cmd: &mut CreateApplicationCommand) -> &mut CreateApplicationCommand {
+    cmd.name("case")
+        .description("Look up and amend moderation cases")
+        .create_option(|option| {
+            option
+                .name("view")
+                .description("Show a case")
+                .kind(CommandOptionType::SubCommand)
+                .create_sub_option(|o| {
+                    o.name("id")
+                        .description("The case number")
+                        .kind(CommandOptionType::Integer)
+                        .min_int_value(1)
+                        .required(true)
+                })
+        })
+        .create_option(|option| {
+            option
+                .name("reason")
+                .description("Change the reason of a case")
+                .kind(CommandOptionType::SubCommand)
+                .create_sub_option(|o| {
+                    o.name("id")
+                        .description("The case number")
+                        .kind(CommandOptionType::Integer)
+                        .min_int_value(1)
+                        .required(true)
+                })
+                .create_sub_option(|o| {
+                    o.name("text")
+                        .description("The new reason")
+                        .kind(CommandOptionType::String)
+                        .required(true)
+                })
+        })
+        .create_option(|option| {
+            option
+                .name("history")
+                .description("List the cases against a member")
+                .kind(CommandOptionType::SubCommand)
+                .create_sub_option(|o| {
+                    o.name("user")
+                        .description("The member to look up")
+                        .kind(CommandOptionType::User)
+                        .required(true)
+                })
+        })
+}
+
+pub async fn run(
+    options: &[CommandDataOption],
+    ctx: &Context,
+    cmd: &ApplicationCommandInteraction,
+    bot: &Bot,
+) -> Result<CreateEmbed> {
+    if !has_role(cmd.member.as_ref(), &bot.settings.roles.staff) {
+        return Err(eyre::eyre!("Only staff can view cases"));
+    }
+
+    let subcommand = options
+        .first()
+        .ok_or(eyre::eyre!("No subcommand provided"))?;
+
+    match subcommand.name.as_str() {
+        "view" => {
+            let number = parse_number(&subcommand.options)?;
+            let case = bot
+                .firestore_manager
+                .case_get(number)
+                .await?
+                .ok_or(eyre::eyre!("Case {} does not exist", number))?;
+            Ok(case_embed(&case))
+        }
+        "reason" => {
+            let number = parse_number(&subcommand.options)?;
+            let text = match parse_option(&subcommand.options, "text")? {
+                CommandDataOptionValue::String(text) => text,
+                _ => Err(eyre::eyre!("No reason provided"))?,
+            };
+            let case = bot
+                .moderation_manager
+                .amend_case(ctx, bot, number, text)
+                .await?;
+            Ok(case_embed(&case))
+        }
+        "history" => {
+            let user = match parse_option(&subcommand.options, "user")? {
+                CommandDataOptionValue::User(user, _member) => user,
+                _ => Err(eyre::eyre!("No user provided"))?,
+            };
+            let cases = bot
+                .firestore_manager
+                .case_history(&user.id.to_string())
+                .await?;
+
+            let mut embed = CreateEmbed::default();
+            embed
+                .title(format!("Cases against {}", user.tag()))
+                .color(Color::BLUE);
+            if cases.is_empty() {
+                embed.description("No cases");
+            }
+            let skip = cases.len().saturating_sub(HISTORY_LIMIT);
+            for case in cases.iter().skip(skip) {
+                embed.field(
+                    format!("#{} | {}", case.number, case.action),
+                    format!(
+                        "{} by <@{}> on {}",
+                        truncate(&case.reason, HISTORY_REASON_LENGTH),
+                        case.moderator,
+                        case.created_on.format("%Y-%m-%d")
+                    ),
+                    false,
+                );
+            }
+            if skip > 0 {
+                embed.footer(|f| f.text(format!("{skip} older cases not shown")));
+            }
+            Ok(embed)
+        }
+        _ => Err(eyre::eyre!("Invalid subcommand provided")),
+    }
+}
+
+fn parse_number(options: &[CommandDataOption]) -> Result<i64> {
+    match parse_option(options, "id")? {
+        CommandDataOptionValue::Integer(number) => Ok(*number),
+        _ => Err(eyre::eyre!("No case number provided")),
+    }
+}
@@ -11,6 +11,7 @@ use serenity::model::Permissions;
 use serenity::prelude::Context;
 use tracing::info;
 
+use super::parse_option;
 use crate::api::bot::Bot;
 use crate::api::schema::circle::Circle;
 
@@ -272,25 +273,3 @@ async fn create_channel(
 
     Ok(res.id)
 }
-
-fn parse_option<'a>(
-    options: &'a [CommandDataOption],
-    name: &'a str,
-) -> Result<&'a CommandDataOptionValue> {
-    let option = options
-        .iter()
-        .find(|o| o.name == name)
-        .ok_or(eyre::eyre!("No {} provided", name));
-
-    let option = match option {
-        Ok(option) => option,
-        Err(e) => return Err(e),
-    };
-
-    let resolved = option
-        .resolved
-        .as_ref()
-        .ok_or(eyre::eyre!("No {} provided", name))?;
-
-    Ok(resolved)
-}
@@ -0,0 +1,72 @@
+use color_eyre::Result;
+use serenity::builder::CreateApplicationCommand;
+use serenity::client::Context;
+use serenity::model::prelude::command::CommandOptionType;
+use serenity::model::prelude::interaction::application_command::{
+    ApplicationCommandInteraction, CommandDataOption,
+};
+
+use crate::api::bot::Bot;
+use crate::util::roles::has_role;
+
+pub fn register(cmd: &mut CreateApplicationCommand) -> &mut CreateApplicationCommand {
+    cmd.name("lockdown")
+        .description("Lock the server down during a raid")
+        .create_option(|option| {
+            option
+                .name("enable")
+                .description("Raise the verification level and stop giving out the member role")
+                .kind(CommandOptionType::SubCommand)
+        })
+        .create_option(|option| {
+            option
+                .name("disable")
+                .description("Lift the lockdown")
+                .kind(CommandOptionType::SubCommand)
+        })
+}
+
+pub async fn run(
+    options: &[CommandDataOption],
+    ctx: &Context,
+    cmd: &ApplicationCommandInteraction,
+    bot: &Bot,
+) -> Result<String> {
+    if !has_role(cmd.member.as_ref(), &bot.settings.roles.staff) {
+        return Err(eyre::eyre!("Only staff can lock the server down"));
+    }
+
+    let subcommand = options
+        .first()
+        .ok_or(eyre::eyre!("No subcommand provided"))?;
+    let locked = match subcommand.name.as_str() {
+        "enable" => {
+            if !bot.raid_manager.lockdown(ctx, bot).await? {
+                return Ok("The server is already locked down".to_string());
+            }
+            true
+        }
+        "disable" => {
+            if !bot.raid_manager.lift_lockdown(ctx, bot).await? {
+                return Ok("The server is not locked down".to_string());
+            }
+            false
+        }
+        _ => Err(eyre::eyre!("Invalid subcommand provided"))?,
+    };
+
+    bot.raid_manager
+        .log_lockdown(ctx, bot, cmd.user.id, locked)
+        .await?;
+    if locked {
+        return Ok("The server has been locked down".to_string());
+    }
+    match bot.verification_manager.grant_pending(ctx, bot).await {
+        Ok(granted) => Ok(format!(
+            "The lockdown has been lifted, {granted} members who verified meanwhile were let in"
+        )),
+        Err(why) => Ok(format!(
+            "The lockdown has been lifted, but the members who verified meanwhile could not be let in: {why}"
+        )),
+    }
+}
@@ -1,4 +1,37 @@
+use color_eyre::Result;
+use serenity::model::prelude::interaction::application_command::{
+    CommandDataOption, CommandDataOptionValue,
+};
+
 pub mod admin;
+pub mod ban;
+pub mod case;
 pub mod circle;
+pub mod kick;
+pub mod lockdown;
+pub mod mute;
 pub mod ping;
-pub mod report;
\ No newline at end of file
+pub mod report;
+pub mod response;
+pub mod rolepanel;
+pub mod strike;
+
+/// Get the resolved value of a command option by name
+/// # Errors
+/// * If the option was not provided
+pub fn parse_option<'a>(
+    options: &'a [CommandDataOption],
+    name: &'a str,
+) -> Result<&'a CommandDataOptionValue> {
+    let option = options
+        .iter()
+        .find(|o| o.name == name)
+        .ok_or(eyre::eyre!("No {} provided", name))?;
+
+    let resolved = option
+        .resolved
+        .as_ref()
+        .ok_or(eyre::eyre!("No {} provided", name))?;
+
+    Ok(resolved)
+}
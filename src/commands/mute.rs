@@ -0,0 +1,107 @@
+use color_eyre::Result;
+use serenity::builder::CreateApplicationCommand;
+use serenity::model::prelude::command::CommandOptionType;
+use serenity::model::prelude::interaction::application_command::{
+    ApplicationCommandInteraction, CommandDataOptionValue,
+};
+use serenity::prelude::Context;
+
+use super::parse_option;
+use crate::api::bot::Bot;
+use crate::util::roles::has_role;
+use crate::util::time::{format_duration, parse_duration};
+
+pub fn register(cmd: &mut CreateApplicationCommand) -> &mut CreateApplicationCommand {
+    cmd.name("mute")
+        .description("Mute a member for a while")
+        .create_option(|option| {
+            option
+                .name("user")
+                .description("The member to mute")
+                .kind(CommandOptionType::User)
+                .required(true)
+        })
+        .create_option(|option| {
+            option
+                .name("duration")
+                .description("How long the mute lasts, e.g. 30m, 12h or 1d12h")
+                .kind(CommandOptionType::String)
+                .required(true)
+        })
+        .create_option(|option| {
+            option
+                .name("reason")
+                .description("Why the member is being muted")
+                .kind(CommandOptionType::String)
+                .required(true)
+        })
+}
+
+pub fn register_unmute(cmd: &mut CreateApplicationCommand) -> &mut CreateApplicationCommand {
+    cmd.name("unmute")
+        .description("Lift the mute of a member")
+        .create_option(|option| {
+            option
+                .name("user")
+                .description("The member to unmute")
+                .kind(CommandOptionType::User)
+                .required(true)
+        })
+}
+
+pub async fn run(ctx: &Context, cmd: &ApplicationCommandInteraction, bot: &Bot) -> Result<String> {
+    if !has_role(cmd.member.as_ref(), &bot.settings.roles.staff) {
+        return Err(eyre::eyre!("Only staff can mute members"));
+    }
+
+    let options = &cmd.data.options;
+    let user = match parse_option(options, "user")? {
+        CommandDataOptionValue::User(user, _member) => user,
+        _ => Err(eyre::eyre!("No user provided"))?,
+    };
+    let duration = match parse_option(options, "duration")? {
+        CommandDataOptionValue::String(duration) => parse_duration(duration).ok_or(eyre::eyre!(
+            "Invalid duration {}, use e.g. 30m, 12h or 1d12h, up to a year",
+            duration
+        ))?,
+        _ => Err(eyre::eyre!("No duration provided"))?,
+    };
+    let reason = match parse_option(options, "reason")? {
+        CommandDataOptionValue::String(reason) => reason,
+        _ => Err(eyre::eyre!("No reason provided"))?,
+    };
+    if user.bot {
+        return Err(eyre::eyre!("Bots cannot be muted"));
+    }
+
+    bot.moderation_manager
+        .mute(ctx, bot, user.id, cmd.user.id, Some(duration), reason)
+        .await?;
+
+    Ok(format!(
+        "{} has been muted for {}",
+        user.tag(),
+        format_duration(duration)
+    ))
+}
+
+pub async fn run_unmute(
+    ctx: &Context,
+    cmd: &ApplicationCommandInteraction,
+    bot: &Bot,
+) -> Result<String> {
+    if !has_role(cmd.member.as_ref(), &bot.settings.roles.staff) {
+        return Err(eyre::eyre!("Only staff can unmute members"));
+    }
+
+    let user = match parse_option(&cmd.data.options, "user")? {
+        CommandDataOptionValue::User(user, _member) => user,
+        _ => Err(eyre::eyre!("No user provided"))?,
+    };
+
+    bot.moderation_manager
+        .unmute(ctx, bot, user.id, cmd.user.id)
+        .await?;
+
+    Ok(format!("{} has been unmuted", user.tag()))
+}
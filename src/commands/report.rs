@@ -1,18 +1,33 @@
+use std::collections::HashMap;
+
+use chrono::{Duration, NaiveDate, TimeZone, Utc};
 use color_eyre::Result;
-use serenity::builder::CreateApplicationCommand;
+use firestore::FirestoreTimestamp;
+use serenity::builder::{CreateApplicationCommand, CreateEmbed};
 use serenity::client::Context;
+use serenity::futures::stream::BoxStream;
+use serenity::futures::StreamExt;
 use serenity::model::prelude::application_command::ApplicationCommandInteraction;
-use serenity::model::prelude::command::CommandType;
+use serenity::model::prelude::command::{CommandOptionType, CommandType};
+use serenity::model::prelude::interaction::application_command::{
+    CommandDataOption, CommandDataOptionValue,
+};
 use serenity::model::prelude::InteractionResponseType;
+use serenity::utils::Color;
+use strum::IntoEnumIterator;
+use tracing::info;
 
+use super::parse_option;
 use crate::api::bot::Bot;
+use crate::api::schema::report::{Report, ReportCategories};
+use crate::util::roles::has_role;
+use crate::util::time::format_duration;
 
 pub fn register(cmd: &mut CreateApplicationCommand) -> &mut CreateApplicationCommand {
-    cmd.name("Anonymous Report")
-        .kind(CommandType::Message)
+    cmd.name("Anonymous Report").kind(CommandType::Message)
 }
 
-pub async fn run(ctx: &Context, cmd: &ApplicationCommandInteraction, bot: &Bot) -> Result<String> {
+pub async fn run(ctx: &Context, cmd: &ApplicationCommandInteraction, bot: &Bot) -> Result<()> {
     let data = cmd.data.resolved.clone();
     let msg: Vec<_> = data.messages.values().collect();
     let Some(msg) = msg.first() else {
@@ -29,7 +44,168 @@ pub async fn run(ctx: &Context, cmd: &ApplicationCommandInteraction, bot: &Bot)
                     .set_embed(embed)
                     .ephemeral(true)
             })
-    }).await?;
+    })
+    .await?;
+
+    Ok(())
+}
+
+pub fn register_slash(cmd: &mut CreateApplicationCommand) -> &mut CreateApplicationCommand {
+    cmd.name("report")
+        .description("Anonymous report tools")
+        .create_option(|option| {
+            option
+                .name("stats")
+                .description("Show report trends for a date range")
+                .kind(CommandOptionType::SubCommand)
+                .create_sub_option(|o| {
+                    o.name("from")
+                        .description(
+                            "The first day to include, as YYYY-MM-DD (default 30 days ago)",
+                        )
+                        .kind(CommandOptionType::String)
+                        .required(false)
+                })
+                .create_sub_option(|o| {
+                    o.name("to")
+                        .description("The last day to include, as YYYY-MM-DD (default today)")
+                        .kind(CommandOptionType::String)
+                        .required(false)
+                })
+        })
+}
+
+pub async fn run_slash(
+    options: &[CommandDataOption],
+    cmd: &ApplicationCommandInteraction,
+    bot: &Bot,
+) -> Result<CreateEmbed> {
+    if !has_role(cmd.member.as_ref(), &bot.settings.roles.staff) {
+        return Err(eyre::eyre!("Only staff can view report stats"));
+    }
+
+    let subcommand = options
+        .first()
+        .ok_or(eyre::eyre!("No subcommand provided"))?;
+
+    match subcommand.name.as_str() {
+        "stats" => stats(&subcommand.options, bot).await,
+        _ => Err(eyre::eyre!("Invalid subcommand provided")),
+    }
+}
+
+async fn stats(options: &[CommandDataOption], bot: &Bot) -> Result<CreateEmbed> {
+    let today = Utc::now().date_naive();
+    let from = parse_date(options, "from")?.unwrap_or(today - Duration::days(30));
+    let to = parse_date(options, "to")?.unwrap_or(today);
+    if from > to {
+        return Err(eyre::eyre!("The start date must be before the end date"));
+    }
+    let start = from
+        .and_hms_opt(0, 0, 0)
+        .ok_or(eyre::eyre!("Invalid date"))?;
+    let end = to
+        .and_hms_opt(23, 59, 59)
+        .ok_or(eyre::eyre!("Invalid date"))?;
+    let (start, end) = (Utc.from_utc_datetime(&start), Utc.from_utc_datetime(&end));
+    info!("Computing report stats from {} to {}", from, to);
+
+    let db = bot.firestore_manager.client.fluent();
+    let reports: BoxStream<Report> = db
+        .select()
+        .from("report")
+        .filter(|q| {
+            q.for_all([
+                q.field("createdOn")
+                    .greater_than_or_equal(FirestoreTimestamp(start)),
+                q.field("createdOn")
+                    .less_than_or_equal(FirestoreTimestamp(end)),
+            ])
+        })
+        .obj()
+        .stream_query()
+        .await?;
+    let reports: Vec<Report> = reports.collect().await;
 
-    Ok("Thank you for your report".to_string())
-}
\ No newline at end of file
+    let mut embed = CreateEmbed::default()
+        .title("Report stats")
+        .description(format!(
+            "{} reports from {} to {}",
+            reports.len(),
+            from.format("%B %d, %Y"),
+            to.format("%B %d, %Y")
+        ))
+        .color(Color::BLUE)
+        .clone();
+    if reports.is_empty() {
+        return Ok(embed);
+    }
+
+    let mut per_category: HashMap<ReportCategories, usize> = HashMap::new();
+    for report in &reports {
+        // Older reports only have the category of the first report
+        match report.categories.is_empty() {
+            true => *per_category.entry(report.category).or_default() += 1,
+            false => {
+                for category in &report.categories {
+                    *per_category.entry(*category).or_default() += 1;
+                }
+            }
+        }
+    }
+    let per_category = ReportCategories::iter()
+        .map(|c| format!("{}: {}", c, per_category.get(&c).unwrap_or(&0)))
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    let mut per_channel: HashMap<&str, usize> = HashMap::new();
+    for report in &reports {
+        *per_channel.entry(report.channel_id.as_str()).or_default() += 1;
+    }
+    let mut per_channel: Vec<(&str, usize)> = per_channel.into_iter().collect();
+    per_channel.sort_by_key(|(_, count)| std::cmp::Reverse(*count));
+    let per_channel = per_channel
+        .iter()
+        .take(10)
+        .map(|(channel, count)| format!("<#{channel}>: {count}"))
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    let mut resolution_times: Vec<Duration> = reports
+        .iter()
+        .filter(|r| !r.status.is_open())
+        .filter_map(|r| r.handled_on.map(|handled| handled - r.created_on))
+        .collect();
+    resolution_times.sort();
+    // An even number of reports has two middle values, the median is halfway between them
+    let middle = resolution_times.len() / 2;
+    let median = match resolution_times.len() {
+        0 => "N/A".to_owned(),
+        len if len % 2 == 0 => {
+            format_duration((resolution_times[middle - 1] + resolution_times[middle]) / 2)
+        }
+        _ => format_duration(resolution_times[middle]),
+    };
+
+    let backlog = reports.iter().filter(|r| r.status.is_open()).count();
+
+    embed
+        .field("**Per category**", per_category, true)
+        .field("**Top channels**", per_channel, true)
+        .field("**Median time to resolution**", median, false)
+        .field("**Open backlog**", backlog, true)
+        .field("**Closed**", reports.len() - backlog, true);
+    Ok(embed)
+}
+
+fn parse_date(options: &[CommandDataOption], name: &str) -> Result<Option<NaiveDate>> {
+    let Ok(value) = parse_option(options, name) else {
+        return Ok(None);
+    };
+    let CommandDataOptionValue::String(value) = value else {
+        return Err(eyre::eyre!("Invalid {} provided", name));
+    };
+    let date = NaiveDate::parse_from_str(value, "%Y-%m-%d")
+        .map_err(|_| eyre::eyre!("Invalid {} date {}, use YYYY-MM-DD", name, value))?;
+    Ok(Some(date))
+}
@@ -0,0 +1,177 @@
+use std::str::FromStr;
+
+use chrono::Duration;
+use color_eyre::Result;
+use serenity::builder::{CreateApplicationCommand, CreateApplicationCommandOption, CreateEmbed};
+use serenity::client::Context;
+use serenity::model::prelude::command::CommandOptionType;
+use serenity::model::prelude::interaction::application_command::{
+    ApplicationCommandInteraction, CommandDataOption, CommandDataOptionValue,
+};
+use serenity::utils::Color;
+use strum::IntoEnumIterator;
+
+use super::parse_option;
+use crate::api::bot::Bot;
+use crate::api::schema::response::{Response, ResponsesType};
+use crate::util::roles::has_role;
+use crate::util::text::truncate;
+use crate::util::time::format_duration;
+
+/// Longest message shown per action in `/response list`, keeps the embed under 6000 characters
+const LIST_MESSAGE_LENGTH: usize = 1000;
+/// Longest message shown by `/response preview`, embed descriptions are limited to 4096 characters
+const PREVIEW_MESSAGE_LENGTH: usize = 4000;
+
+pub fn register(cmd: &mut CreateApplicationCommand) -> &mut CreateApplicationCommand {
+    cmd.name("response")
+        .description("Manage the messages sent to members on moderation actions")
+        .create_option(|option| {
+            option
+                .name("set")
+                .description("Set the message for an action")
+                .kind(CommandOptionType::SubCommand)
+                .create_sub_option(type_option)
+                .create_sub_option(|o| {
+                    o.name("message")
+                        .description("The message, can use {user}, {reason} and {duration}")
+                        .kind(CommandOptionType::String)
+                        .required(true)
+                })
+        })
+        .create_option(|option| {
+            option
+                .name("delete")
+                .description("Go back to the default message for an action")
+                .kind(CommandOptionType::SubCommand)
+                .create_sub_option(type_option)
+        })
+        .create_option(|option| {
+            option
+                .name("list")
+                .description("List the message of every action")
+                .kind(CommandOptionType::SubCommand)
+        })
+        .create_option(|option| {
+            option
+                .name("preview")
+                .description("Show the message for an action as a member would see it")
+                .kind(CommandOptionType::SubCommand)
+                .create_sub_option(type_option)
+        })
+}
+
+fn type_option(option: &mut CreateApplicationCommandOption) -> &mut CreateApplicationCommandOption {
+    option
+        .name("type")
+        .description("The moderation action")
+        .kind(CommandOptionType::String)
+        .required(true);
+    for tipe in ResponsesType::iter() {
+        option.add_string_choice(tipe.to_string(), tipe.to_string());
+    }
+    option
+}
+
+pub async fn run(
+    options: &[CommandDataOption],
+    ctx: &Context,
+    cmd: &ApplicationCommandInteraction,
+    bot: &Bot,
+) -> Result<CreateEmbed> {
+    if !has_role(cmd.member.as_ref(), &bot.settings.roles.director) {
+        return Err(eyre::eyre!("Only directors can manage responses"));
+    }
+
+    let subcommand = options
+        .first()
+        .ok_or(eyre::eyre!("No subcommand provided"))?;
+
+    match subcommand.name.as_str() {
+        "set" => {
+            let tipe = parse_type(&subcommand.options)?;
+            let message = match parse_option(&subcommand.options, "message")? {
+                CommandDataOptionValue::String(message) => message,
+                _ => Err(eyre::eyre!("No message provided"))?,
+            };
+            bot.firestore_manager
+                .response_add(ctx, tipe, message)
+                .await?;
+            Ok(CreateEmbed::default()
+                .title(format!("Set the {tipe} response"))
+                .description(message)
+                .color(Color::DARK_GREEN)
+                .clone())
+        }
+        "delete" => {
+            let tipe = parse_type(&subcommand.options)?;
+            bot.firestore_manager.response_delete(ctx, tipe).await?;
+            Ok(CreateEmbed::default()
+                .title(format!("Deleted the {tipe} response"))
+                .description("Members will get the default message for this action")
+                .color(Color::DARK_GREEN)
+                .clone())
+        }
+        "list" => list(ctx).await,
+        "preview" => {
+            let tipe = parse_type(&subcommand.options)?;
+            preview(ctx, cmd, tipe).await
+        }
+        _ => Err(eyre::eyre!("Invalid subcommand provided")),
+    }
+}
+
+async fn list(ctx: &Context) -> Result<CreateEmbed> {
+    let data = ctx.data.read().await;
+    let responses = data
+        .get::<Response>()
+        .ok_or(eyre::eyre!("Unable to get responses"))?;
+
+    let mut embed = CreateEmbed::default();
+    embed.title("Moderation Responses").color(Color::BLUE);
+    for tipe in ResponsesType::iter() {
+        let message = responses
+            .get(&tipe.to_string())
+            .map_or("*Default message*".to_string(), |response| {
+                truncate(&response.message, LIST_MESSAGE_LENGTH)
+            });
+        embed.field(tipe.to_string(), message, false);
+    }
+    Ok(embed)
+}
+
+async fn preview(
+    ctx: &Context,
+    cmd: &ApplicationCommandInteraction,
+    tipe: ResponsesType,
+) -> Result<CreateEmbed> {
+    let data = ctx.data.read().await;
+    let response = data
+        .get::<Response>()
+        .ok_or(eyre::eyre!("Unable to get responses"))?
+        .get(&tipe.to_string())
+        .ok_or(eyre::eyre!("No {} response is set", tipe))?;
+
+    let duration = format_duration(Duration::days(1));
+    Ok(CreateEmbed::default()
+        .title(format!("Preview of the {tipe} response"))
+        .description(truncate(
+            &response.render(
+                &format!("<@{}>", cmd.user.id),
+                "Example reason",
+                Some(&duration),
+            ),
+            PREVIEW_MESSAGE_LENGTH,
+        ))
+        .color(Color::BLUE)
+        .clone())
+}
+
+fn parse_type(options: &[CommandDataOption]) -> Result<ResponsesType> {
+    match parse_option(options, "type")? {
+        CommandDataOptionValue::String(tipe) => {
+            ResponsesType::from_str(tipe).map_err(|_| eyre::eyre!("Invalid response type {}", tipe))
+        }
+        _ => Err(eyre::eyre!("No response type provided")),
+    }
+}
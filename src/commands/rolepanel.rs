@@ -0,0 +1,24 @@
+use color_eyre::Result;
+use serenity::builder::CreateApplicationCommand;
+use serenity::client::Context;
+use serenity::model::prelude::interaction::application_command::ApplicationCommandInteraction;
+
+use crate::api::bot::Bot;
+use crate::util::roles::has_role;
+
+pub fn register(cmd: &mut CreateApplicationCommand) -> &mut CreateApplicationCommand {
+    cmd.name("rolepanel")
+        .description("Post or refresh the division role panel in the roles channel")
+}
+
+pub async fn run(ctx: &Context, cmd: &ApplicationCommandInteraction, bot: &Bot) -> Result<String> {
+    if !has_role(cmd.member.as_ref(), &bot.settings.roles.staff) {
+        return Err(eyre::eyre!("Only staff can post the role panel"));
+    }
+
+    let posted = bot.division_manager.post_panel(ctx, bot).await?;
+    Ok(match posted {
+        true => "The role panel has been posted".to_string(),
+        false => "The role panel has been updated".to_string(),
+    })
+}
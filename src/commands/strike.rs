@@ -0,0 +1,63 @@
+use color_eyre::Result;
+use serenity::builder::CreateApplicationCommand;
+use serenity::model::prelude::command::CommandOptionType;
+use serenity::model::prelude::interaction::application_command::{
+    ApplicationCommandInteraction, CommandDataOptionValue,
+};
+use serenity::prelude::Context;
+
+use super::parse_option;
+use crate::api::bot::Bot;
+use crate::util::roles::has_role;
+
+pub fn register(cmd: &mut CreateApplicationCommand) -> &mut CreateApplicationCommand {
+    cmd.name("strike")
+        .description("Give a member a strike")
+        .create_option(|option| {
+            option
+                .name("user")
+                .description("The member to strike")
+                .kind(CommandOptionType::User)
+                .required(true)
+        })
+        .create_option(|option| {
+            option
+                .name("reason")
+                .description("Why the member is getting a strike")
+                .kind(CommandOptionType::String)
+                .required(true)
+        })
+}
+
+pub async fn run(ctx: &Context, cmd: &ApplicationCommandInteraction, bot: &Bot) -> Result<String> {
+    if !has_role(cmd.member.as_ref(), &bot.settings.roles.staff) {
+        return Err(eyre::eyre!("Only staff can give strikes"));
+    }
+
+    let options = &cmd.data.options;
+    let user = match parse_option(options, "user")? {
+        CommandDataOptionValue::User(user, _member) => user,
+        _ => Err(eyre::eyre!("No user provided"))?,
+    };
+    let reason = match parse_option(options, "reason")? {
+        CommandDataOptionValue::String(reason) => reason,
+        _ => Err(eyre::eyre!("No reason provided"))?,
+    };
+    if user.bot {
+        return Err(eyre::eyre!("Bots cannot be given strikes"));
+    }
+
+    bot.moderation_manager
+        .check_hierarchy(ctx, user.id, cmd.user.id)
+        .await?;
+    let member = bot
+        .moderation_manager
+        .strike(ctx, bot, user.id, cmd.user.id, reason, None)
+        .await?;
+
+    Ok(format!(
+        "{} has been given a strike, they now have {}",
+        user.tag(),
+        member.strikes
+    ))
+}
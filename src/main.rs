@@ -3,9 +3,12 @@ use std::{collections::HashMap, fs};
 use serenity::prelude::*;
 use tracing::{debug, warn};
 
-use api::{bot::Bot, schema::circle::Circle};
+use api::{
+    bot::Bot,
+    schema::{circle::Circle, report::Report, response::Response},
+};
 
-use crate::managers::report::Report;
+use crate::managers::report::{PendingReport, ReporterActivity, ReporterContact};
 
 mod api;
 mod commands;
@@ -35,7 +38,10 @@ async fn main() {
         | GatewayIntents::GUILD_INTEGRATIONS
         | GatewayIntents::GUILD_EMOJIS_AND_STICKERS
         | GatewayIntents::GUILD_VOICE_STATES
-        | GatewayIntents::DIRECT_MESSAGE_REACTIONS;
+        | GatewayIntents::DIRECT_MESSAGE_REACTIONS
+        // Privileged, has to be enabled under Bot > Privileged Gateway Intents in the
+        // developer portal, without it automod and the audit log only see empty messages
+        | GatewayIntents::MESSAGE_CONTENT;
 
     let mut client = Client::builder(bot.settings.token.clone(), intents)
         .event_handler(bot)
@@ -46,6 +52,10 @@ async fn main() {
         let mut data = client.data.write().await;
         data.insert::<Circle>(HashMap::new());
         data.insert::<Report>(HashMap::new());
+        data.insert::<PendingReport>(HashMap::new());
+        data.insert::<ReporterActivity>(HashMap::new());
+        data.insert::<ReporterContact>(HashMap::new());
+        data.insert::<Response>(HashMap::new());
     }
 
     if let Err(why) = client.start().await {
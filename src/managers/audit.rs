@@ -0,0 +1,302 @@
+use std::borrow::Cow;
+use std::collections::{HashMap, VecDeque};
+use std::sync::Arc;
+
+use color_eyre::Result;
+use serenity::builder::CreateEmbed;
+use serenity::client::Context;
+use serenity::model::event::MessageUpdateEvent;
+use serenity::model::prelude::{AttachmentType, ChannelId, Message, MessageId, UserId};
+use serenity::prelude::Mutex;
+use serenity::utils::Color;
+use tracing::{debug, instrument};
+
+use crate::settings::Settings;
+use crate::util::text::truncate;
+
+/// How many messages are remembered so their content can be logged once they change
+const CACHE_SIZE: usize = 5000;
+
+/// Longest content shown in a single embed field
+const FIELD_LENGTH: usize = 1000;
+
+/// What is remembered about a message
+#[derive(Clone)]
+struct CachedMessage {
+    author: UserId,
+    channel_id: ChannelId,
+    content: String,
+    attachments: Vec<String>,
+}
+
+impl From<&Message> for CachedMessage {
+    fn from(msg: &Message) -> Self {
+        Self {
+            author: msg.author.id,
+            channel_id: msg.channel_id,
+            content: msg.content.clone(),
+            attachments: msg.attachments.iter().map(|a| a.url.clone()).collect(),
+        }
+    }
+}
+
+/// The latest messages, the oldest ones are forgotten once the cache is full
+#[derive(Default)]
+struct MessageCache {
+    messages: HashMap<MessageId, CachedMessage>,
+    order: VecDeque<MessageId>,
+}
+
+impl MessageCache {
+    fn insert(&mut self, id: MessageId, message: CachedMessage) {
+        if self.messages.insert(id, message).is_none() {
+            self.order.push_back(id);
+        }
+        while self.order.len() > CACHE_SIZE {
+            if let Some(oldest) = self.order.pop_front() {
+                self.messages.remove(&oldest);
+            }
+        }
+    }
+
+    fn remove(&mut self, id: MessageId) -> Option<CachedMessage> {
+        let message = self.messages.remove(&id)?;
+        self.order.retain(|cached| *cached != id);
+        Some(message)
+    }
+}
+
+/// Logs edited and deleted messages, their content is only sent to the bot with the
+/// privileged message content intent requested in `main`
+#[derive(Clone)]
+pub struct AuditManager {
+    log_channel: Option<ChannelId>,
+    ignored_channels: Vec<ChannelId>,
+    cache: Arc<Mutex<MessageCache>>,
+}
+
+impl AuditManager {
+    #[must_use]
+    pub fn new(settings: &Settings) -> Self {
+        let log_channel = settings.channels.audit.parse::<u64>().ok().map(ChannelId);
+        // The circle cards are deleted and reposted whenever the circles are recached
+        let ignored_channels = std::iter::once(ChannelId(settings.circles.join_channel))
+            .chain(log_channel)
+            .collect();
+        Self {
+            log_channel,
+            ignored_channels,
+            cache: Arc::new(Mutex::new(MessageCache::default())),
+        }
+    }
+
+    fn is_ignored(&self, channel_id: ChannelId) -> bool {
+        self.log_channel.is_none() || self.ignored_channels.contains(&channel_id)
+    }
+
+    /// Remember a new message so it can be logged if it is edited or deleted
+    pub async fn remember(&self, msg: &Message) {
+        if msg.author.bot || msg.guild_id.is_none() || self.is_ignored(msg.channel_id) {
+            return;
+        }
+        self.cache.lock().await.insert(msg.id, msg.into());
+    }
+
+    /// Log the content of a message before and after it was edited
+    /// # Arguments
+    /// * `ctx` - The context of the event
+    /// * `event` - The new data of the message
+    /// # Errors
+    /// * If the edit cannot be logged
+    #[instrument(skip(self, ctx, event))]
+    pub async fn message_edited(&self, ctx: &Context, event: &MessageUpdateEvent) -> Result<()> {
+        // Embeds being resolved also show up as updates, only content changes matter
+        let Some(content) = &event.content else {
+            return Ok(());
+        };
+        if event.guild_id.is_none() || self.is_ignored(event.channel_id) {
+            return Ok(());
+        }
+        if matches!(&event.author, Some(author) if author.bot) {
+            return Ok(());
+        }
+
+        let before = {
+            let mut cache = self.cache.lock().await;
+            let before = cache.messages.get(&event.id).cloned();
+            match (&before, &event.author) {
+                (Some(cached), _) => {
+                    let mut edited = cached.clone();
+                    edited.content = content.clone();
+                    cache.insert(event.id, edited);
+                }
+                (None, Some(author)) => cache.insert(
+                    event.id,
+                    CachedMessage {
+                        author: author.id,
+                        channel_id: event.channel_id,
+                        content: content.clone(),
+                        attachments: Vec::new(),
+                    },
+                ),
+                (None, None) => {}
+            }
+            before
+        };
+        if matches!(&before, Some(before) if before.content == *content) {
+            return Ok(());
+        }
+
+        let author = match (&before, &event.author) {
+            (Some(before), _) => before.author,
+            (None, Some(author)) => author.id,
+            (None, None) => {
+                debug!("Skipping edit of {} from an unknown author", event.id);
+                return Ok(());
+            }
+        };
+        let before = match before {
+            Some(before) => truncate(&before.content, FIELD_LENGTH),
+            None => "*Not cached*".to_string(),
+        };
+        let link = match event.guild_id {
+            Some(guild_id) => format!(
+                "[Jump to message](https://discord.com/channels/{}/{}/{})",
+                guild_id, event.channel_id, event.id
+            ),
+            None => String::new(),
+        };
+
+        let embed = CreateEmbed::default()
+            .title("Message Edited")
+            .color(Color::GOLD)
+            .description(link)
+            .field("**Author**", format!("<@{author}>"), true)
+            .field("**Channel**", format!("<#{}>", event.channel_id), true)
+            .field("**Before**", before, false)
+            .field("**After**", truncate(content, FIELD_LENGTH), false)
+            .clone();
+        self.log(ctx, embed, None).await
+    }
+
+    /// Log the content of a deleted message
+    /// # Arguments
+    /// * `ctx` - The context of the event
+    /// * `channel_id` - The channel the message was in
+    /// * `message_id` - The deleted message
+    /// # Errors
+    /// * If the deletion cannot be logged
+    #[instrument(skip(self, ctx))]
+    pub async fn message_deleted(
+        &self,
+        ctx: &Context,
+        channel_id: ChannelId,
+        message_id: MessageId,
+    ) -> Result<()> {
+        if self.is_ignored(channel_id) {
+            return Ok(());
+        }
+        // Messages that were never cached are from bots or from before the bot started
+        let Some(message) = self.cache.lock().await.remove(message_id) else {
+            return Ok(());
+        };
+
+        let mut embed = CreateEmbed::default();
+        embed
+            .title("Message Deleted")
+            .color(Color::RED)
+            .field("**Author**", format!("<@{}>", message.author), true)
+            .field("**Channel**", format!("<#{}>", message.channel_id), true)
+            .field(
+                "**Content**",
+                truncate(&message.content, FIELD_LENGTH),
+                false,
+            );
+        if !message.attachments.is_empty() {
+            embed.field(
+                "**Attachments**",
+                truncate(&message.attachments.join("\n"), FIELD_LENGTH),
+                false,
+            );
+        }
+        self.log(ctx, embed, None).await
+    }
+
+    /// Log the content of messages deleted at once, the full transcript is attached as a file
+    /// # Arguments
+    /// * `ctx` - The context of the event
+    /// * `channel_id` - The channel the messages were in
+    /// * `message_ids` - The deleted messages
+    /// # Errors
+    /// * If the deletion cannot be logged
+    #[instrument(skip(self, ctx, message_ids))]
+    pub async fn messages_deleted(
+        &self,
+        ctx: &Context,
+        channel_id: ChannelId,
+        message_ids: &[MessageId],
+    ) -> Result<()> {
+        if self.is_ignored(channel_id) {
+            return Ok(());
+        }
+        let mut deleted = {
+            let mut cache = self.cache.lock().await;
+            message_ids
+                .iter()
+                .filter_map(|id| Some((*id, cache.remove(*id)?)))
+                .collect::<Vec<_>>()
+        };
+        if deleted.is_empty() {
+            return Ok(());
+        }
+        // Message ids grow over time, so this puts the messages in the order they were sent
+        deleted.sort_by_key(|(id, _)| *id);
+
+        let transcript = deleted
+            .iter()
+            .map(|(id, message)| {
+                let mut line = format!("[{}] {}: {}", id, message.author, message.content);
+                for attachment in &message.attachments {
+                    line.push_str(&format!("\n    {attachment}"));
+                }
+                line
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
+        let embed = CreateEmbed::default()
+            .title("Messages Bulk Deleted")
+            .color(Color::DARK_RED)
+            .field("**Channel**", format!("<#{channel_id}>"), true)
+            .field(
+                "**Messages**",
+                format!("{} of {} cached", deleted.len(), message_ids.len()),
+                true,
+            )
+            .clone();
+        let file = AttachmentType::Bytes {
+            data: Cow::Owned(transcript.into_bytes()),
+            filename: format!("deleted-{channel_id}.txt"),
+        };
+        self.log(ctx, embed, Some(file)).await
+    }
+
+    async fn log(
+        &self,
+        ctx: &Context,
+        embed: CreateEmbed,
+        file: Option<AttachmentType<'_>>,
+    ) -> Result<()> {
+        let log_channel = self
+            .log_channel
+            .ok_or(eyre::eyre!("The audit channel is not configured"))?;
+        log_channel
+            .send_message(&ctx.http, |m| {
+                if let Some(file) = file {
+                    m.add_file(file);
+                }
+                m.set_embed(embed)
+            })
+            .await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,284 @@
+use std::collections::{HashMap, VecDeque};
+use std::sync::Arc;
+
+use chrono::{DateTime, Duration, Utc};
+use color_eyre::Result;
+use regex::Regex;
+use serenity::builder::CreateEmbed;
+use serenity::client::Context;
+use serenity::model::prelude::{Message, RoleId, UserId};
+use serenity::prelude::Mutex;
+use serenity::utils::Color;
+use tracing::{info, instrument, warn};
+
+use crate::api::bot::Bot;
+use crate::settings::{AutomodAction, AutomodCheck, AutomodRule, Settings};
+use crate::util::text::truncate;
+use crate::util::time::bounded_minutes;
+
+/// How many recent messages are remembered per member for the repeated message check
+const HISTORY_SIZE: usize = 20;
+
+/// The longest window the repeated message check looks back over
+const MAX_WINDOW_SECS: i64 = 60 * 60;
+
+/// The recent messages of each member with when they were sent
+type History = HashMap<UserId, VecDeque<(String, DateTime<Utc>)>>;
+
+/// A rule with its regular expressions compiled up front
+struct CompiledRule {
+    rule: AutomodRule,
+    patterns: Vec<Regex>,
+}
+
+#[derive(Clone)]
+pub struct AutomodManager {
+    enabled: bool,
+    exempt_roles: Vec<RoleId>,
+    rules: Arc<Vec<CompiledRule>>,
+    history: Arc<Mutex<History>>,
+    /// How long messages are remembered, the longest window of the repeated message rules
+    history_window: Duration,
+}
+
+impl AutomodManager {
+    #[must_use]
+    pub fn new(settings: &Settings) -> Self {
+        let automod = &settings.automod;
+        let exempt_roles = automod
+            .exempt_roles
+            .iter()
+            .chain(std::iter::once(&settings.roles.staff))
+            .filter_map(|role| role.parse::<u64>().ok())
+            .map(RoleId)
+            .collect();
+        let rules: Vec<CompiledRule> = automod
+            .rules
+            .iter()
+            .filter_map(|rule| match compile(&rule.check) {
+                Ok(patterns) => {
+                    let mut rule = rule.clone();
+                    if let AutomodCheck::Repeated { window_secs, .. } = &mut rule.check {
+                        *window_secs = (*window_secs).clamp(1, MAX_WINDOW_SECS);
+                    }
+                    Some(CompiledRule { rule, patterns })
+                }
+                Err(why) => {
+                    warn!("Skipping automod rule {:?}: {:?}", rule.check, why);
+                    None
+                }
+            })
+            .collect();
+
+        let history_window = rules
+            .iter()
+            .filter_map(|compiled| match compiled.rule.check {
+                AutomodCheck::Repeated { window_secs, .. } => Some(window_secs),
+                _ => None,
+            })
+            .max()
+            .map_or_else(Duration::zero, Duration::seconds);
+
+        Self {
+            enabled: automod.enabled,
+            exempt_roles,
+            rules: Arc::new(rules),
+            history: Arc::new(Mutex::new(HashMap::new())),
+            history_window,
+        }
+    }
+
+    /// Check a new message against the automod rules and apply the first rule it breaks
+    /// # Arguments
+    /// * `ctx` - The context of the message
+    /// * `bot` - The bot, used for moderation actions
+    /// * `msg` - The new message
+    /// # Errors
+    /// * If the action of the broken rule cannot be applied
+    #[instrument(skip(self, ctx, bot, msg))]
+    pub async fn check(&self, ctx: &Context, bot: &Bot, msg: &Message) -> Result<()> {
+        if !self.enabled || msg.author.bot || msg.guild_id.is_none() || self.is_exempt(msg) {
+            return Ok(());
+        }
+
+        let history = self.remember(msg).await;
+        let channel = msg.channel_id.to_string();
+        for compiled in self.rules.iter() {
+            let rule = &compiled.rule;
+            if rule.ignored_channels.contains(&channel)
+                || (!rule.channels.is_empty() && !rule.channels.contains(&channel))
+            {
+                continue;
+            }
+            if let Some(reason) = breaks(compiled, msg, &history) {
+                info!("{} broke automod rule: {}", msg.author.id, reason);
+                return self.apply(ctx, bot, msg, rule, reason).await;
+            }
+        }
+        Ok(())
+    }
+
+    fn is_exempt(&self, msg: &Message) -> bool {
+        let Some(member) = &msg.member else {
+            return false;
+        };
+        member
+            .roles
+            .iter()
+            .any(|role| self.exempt_roles.contains(role))
+    }
+
+    /// Add a message to the author's recent messages and get the ones from the last while,
+    /// messages older than every window are forgotten so quiet members are not kept around
+    async fn remember(&self, msg: &Message) -> Vec<(String, DateTime<Utc>)> {
+        let now = Utc::now();
+        let since = now - self.history_window;
+        let mut history = self.history.lock().await;
+        history.retain(|_, recent| {
+            while matches!(recent.front(), Some((_, sent_on)) if *sent_on < since) {
+                recent.pop_front();
+            }
+            !recent.is_empty()
+        });
+        let recent = history.entry(msg.author.id).or_default();
+        recent.push_back((normalize(&msg.content), now));
+        while recent.len() > HISTORY_SIZE {
+            recent.pop_front();
+        }
+        recent.iter().cloned().collect()
+    }
+
+    async fn apply(
+        &self,
+        ctx: &Context,
+        bot: &Bot,
+        msg: &Message,
+        rule: &AutomodRule,
+        reason: &str,
+    ) -> Result<()> {
+        if let Err(why) = msg.delete(&ctx.http).await {
+            warn!("Unable to delete message {}: {:?}", msg.id, why);
+        }
+
+        let user = msg.author.id;
+        let reason = format!("Automod: {reason}");
+        match rule.action {
+            AutomodAction::Delete => {}
+            AutomodAction::Warn => {
+                msg.channel_id
+                    .say(
+                        &ctx.http,
+                        format!("<@{user}> your message was removed. {reason}"),
+                    )
+                    .await?;
+            }
+            AutomodAction::Strike => {
+                let moderator = bot.user_id()?;
+                bot.moderation_manager
+                    .strike(ctx, bot, user, moderator, &reason, None)
+                    .await?;
+                return Ok(());
+            }
+            AutomodAction::Mute => {
+                let moderator = bot.user_id()?;
+                let duration = bounded_minutes(rule.mute_minutes).ok_or(eyre::eyre!(
+                    "Invalid automod mute of {} minutes",
+                    rule.mute_minutes
+                ))?;
+                bot.moderation_manager
+                    .mute(ctx, bot, user, moderator, Some(duration), &reason)
+                    .await?;
+                return Ok(());
+            }
+        }
+
+        // Strikes and mutes open a case, smaller actions are only logged
+        let embed = CreateEmbed::default()
+            .title("Automod")
+            .color(Color::ORANGE)
+            .field("**Member**", format!("<@{user}>"), true)
+            .field("**Channel**", format!("<#{}>", msg.channel_id), true)
+            .field("**Action**", format!("{:?}", rule.action), true)
+            .field("**Reason**", reason, false)
+            .field("**Content**", truncate(&msg.content, 1000), false)
+            .clone();
+        bot.moderation_manager.log(ctx, embed).await?;
+        Ok(())
+    }
+}
+
+/// Build the regular expressions a check needs
+fn compile(check: &AutomodCheck) -> Result<Vec<Regex>> {
+    let patterns = match check {
+        AutomodCheck::BannedWords { words } if words.is_empty() => Vec::new(),
+        AutomodCheck::BannedWords { words } => {
+            let words: Vec<String> = words.iter().map(|word| regex::escape(word)).collect();
+            vec![Regex::new(&format!(r"(?i)\b(?:{})\b", words.join("|")))?]
+        }
+        AutomodCheck::Regex { patterns } => patterns
+            .iter()
+            .map(|pattern| Regex::new(pattern))
+            .collect::<Result<_, _>>()?,
+        AutomodCheck::InviteLinks => vec![Regex::new(
+            r"(?i)(?:discord\.gg|discord(?:app)?\.com/invite)/[\w-]+",
+        )?],
+        AutomodCheck::MassMentions { .. }
+        | AutomodCheck::Caps { .. }
+        | AutomodCheck::Repeated { .. } => Vec::new(),
+    };
+    Ok(patterns)
+}
+
+/// Get why a message breaks a rule, if it does
+fn breaks(
+    compiled: &CompiledRule,
+    msg: &Message,
+    history: &[(String, DateTime<Utc>)],
+) -> Option<&'static str> {
+    let content = &msg.content;
+    let broken = match &compiled.rule.check {
+        AutomodCheck::BannedWords { .. }
+        | AutomodCheck::Regex { .. }
+        | AutomodCheck::InviteLinks => compiled
+            .patterns
+            .iter()
+            .any(|pattern| pattern.is_match(content)),
+        AutomodCheck::MassMentions { max } => {
+            msg.mention_everyone || msg.mentions.len() + msg.mention_roles.len() > *max
+        }
+        AutomodCheck::Caps {
+            min_length,
+            max_percent,
+        } => {
+            let letters = content.chars().filter(|c| c.is_alphabetic()).count();
+            let upper = content.chars().filter(|c| c.is_uppercase()).count();
+            letters >= *min_length && upper * 100 > letters * max_percent
+        }
+        AutomodCheck::Repeated { count, window_secs } => {
+            let since = Utc::now() - Duration::seconds(*window_secs);
+            match history.last() {
+                Some((last, _)) if !last.is_empty() => {
+                    history
+                        .iter()
+                        .filter(|(content, sent_on)| content == last && *sent_on >= since)
+                        .count()
+                        >= *count
+                }
+                _ => false,
+            }
+        }
+    };
+
+    broken.then_some(match compiled.rule.check {
+        AutomodCheck::BannedWords { .. } => "Used a banned word",
+        AutomodCheck::Regex { .. } => "Matched a blocked pattern",
+        AutomodCheck::InviteLinks => "Posted an invite link",
+        AutomodCheck::MassMentions { .. } => "Mentioned too many people",
+        AutomodCheck::Caps { .. } => "Used too many capital letters",
+        AutomodCheck::Repeated { .. } => "Sent the same message repeatedly",
+    })
+}
+
+fn normalize(content: &str) -> String {
+    content.trim().to_lowercase()
+}
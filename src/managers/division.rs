@@ -0,0 +1,206 @@
+use color_eyre::Result;
+use serenity::{
+    builder::{CreateActionRow, CreateEmbed},
+    model::prelude::{
+        component::{ActionRowComponent, ButtonStyle},
+        interaction::message_component::MessageComponentInteraction,
+        ChannelId, GuildId, Message, ReactionType, RoleId,
+    },
+    prelude::Context,
+    utils::Color,
+};
+use tracing::{info, instrument, warn};
+
+use crate::api::bot::Bot;
+use crate::settings::{RoleOption, Settings};
+
+/// Discord allows five buttons in a row and five rows in a message
+const BUTTONS_PER_ROW: usize = 5;
+const MAX_BUTTONS: usize = 25;
+
+#[derive(Clone)]
+pub struct DivisionManager {
+    guild_id: GuildId,
+    channel: Option<ChannelId>,
+    title: String,
+    description: String,
+    options: Vec<RoleOption>,
+}
+
+impl DivisionManager {
+    #[must_use]
+    pub fn new(settings: &Settings) -> Self {
+        let options = settings
+            .role_panel
+            .options(&settings.roles.divisions)
+            .into_iter()
+            .filter(|option| option.role.parse::<u64>().is_ok())
+            .take(MAX_BUTTONS)
+            .collect();
+        Self {
+            guild_id: GuildId(settings.guild),
+            channel: settings.channels.roles.parse::<u64>().ok().map(ChannelId),
+            title: settings.role_panel.title.clone(),
+            description: settings.role_panel.description.clone(),
+            options,
+        }
+    }
+
+    /// Post the role panel, or update the one already in the roles channel
+    /// # Returns
+    /// Whether a new panel was posted
+    /// # Errors
+    /// * If the roles channel is not configured
+    /// * If there are no roles to put on the panel
+    /// * If the channel cannot be read or posted to
+    #[instrument(skip(self, ctx, bot))]
+    pub async fn post_panel(&self, ctx: &Context, bot: &Bot) -> Result<bool> {
+        let channel = self
+            .channel
+            .ok_or(eyre::eyre!("The roles channel is not configured"))?;
+        if self.options.is_empty() {
+            return Err(eyre::eyre!("No roles are configured for the panel"));
+        }
+
+        let bot_id = bot.user_id()?;
+        let messages = channel.messages(&ctx.http, |m| m.limit(50)).await?;
+        let mut panels = messages
+            .iter()
+            .filter(|msg| msg.author.id == bot_id && is_panel(msg));
+
+        let embed = self.embed();
+        let rows = self.rows()?;
+        let Some(panel) = panels.next() else {
+            channel
+                .send_message(&ctx.http, |m| {
+                    m.set_embed(embed).components(|c| c.set_action_rows(rows))
+                })
+                .await?;
+            info!("Posted the role panel");
+            return Ok(true);
+        };
+
+        channel
+            .edit_message(&ctx.http, panel.id, |m| {
+                m.set_embed(embed).components(|c| c.set_action_rows(rows))
+            })
+            .await?;
+        // Only one panel should be left in the channel
+        for duplicate in panels {
+            if let Err(why) = duplicate.delete(&ctx.http).await {
+                warn!("Unable to delete duplicate role panel: {:?}", why);
+            }
+        }
+        info!("Updated the role panel");
+        Ok(false)
+    }
+
+    /// Give or take away the role of a button on the panel
+    /// # Arguments
+    /// * `ctx` - The context of the interaction
+    /// * `int` - The button interaction, with a custom id of `division/<role>`
+    /// # Returns
+    /// The confirmation for the member
+    /// # Errors
+    /// * If the role is not on the panel
+    /// * If the role cannot be changed
+    #[instrument(skip(self, ctx, int))]
+    pub async fn handle_button(
+        &self,
+        ctx: &Context,
+        int: &MessageComponentInteraction,
+    ) -> Result<String> {
+        let role = int
+            .data
+            .custom_id
+            .strip_prefix("division/")
+            .ok_or(eyre::eyre!("Unable to get role"))?;
+        // Only roles from the settings can be toggled, whatever the button says
+        let option = self
+            .options
+            .iter()
+            .find(|option| option.role == role)
+            .ok_or(eyre::eyre!("This role can no longer be picked"))?;
+        let role_id = RoleId(option.role.parse::<u64>()?);
+        let member = int
+            .member
+            .as_ref()
+            .ok_or(eyre::eyre!("Roles can only be picked in the server"))?;
+
+        if member.roles.contains(&role_id) {
+            ctx.http
+                .remove_member_role(
+                    self.guild_id.0,
+                    member.user.id.0,
+                    role_id.0,
+                    Some("Role panel"),
+                )
+                .await?;
+            Ok(format!("You left **{}**", option.label))
+        } else {
+            ctx.http
+                .add_member_role(
+                    self.guild_id.0,
+                    member.user.id.0,
+                    role_id.0,
+                    Some("Role panel"),
+                )
+                .await?;
+            Ok(format!("You joined **{}**", option.label))
+        }
+    }
+
+    fn embed(&self) -> CreateEmbed {
+        let roles = self
+            .options
+            .iter()
+            .map(|option| match &option.emoji {
+                Some(emoji) => format!("{} <@&{}>", emoji, option.role),
+                None => format!("<@&{}>", option.role),
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
+        CreateEmbed::default()
+            .title(&self.title)
+            .description(format!("{}\n\n{}", self.description, roles))
+            .color(Color::BLUE)
+            .clone()
+    }
+
+    fn rows(&self) -> Result<Vec<CreateActionRow>> {
+        self.options
+            .chunks(BUTTONS_PER_ROW)
+            .map(|options| {
+                let mut row = CreateActionRow::default();
+                for option in options {
+                    let emoji = option
+                        .emoji
+                        .clone()
+                        .map(ReactionType::try_from)
+                        .transpose()?;
+                    row.create_button(|b| {
+                        b.custom_id(format!("division/{}", option.role))
+                            .label(&option.label)
+                            .style(ButtonStyle::Secondary);
+                        if let Some(emoji) = emoji {
+                            b.emoji(emoji);
+                        }
+                        b
+                    });
+                }
+                Ok(row)
+            })
+            .collect()
+    }
+}
+
+/// Whether a message is the role panel
+fn is_panel(msg: &Message) -> bool {
+    msg.components
+        .iter()
+        .flat_map(|row| row.components.iter())
+        .any(|component| {
+            matches!(component, ActionRowComponent::Button(button)
+                if matches!(&button.custom_id, Some(id) if id.starts_with("division/")))
+        })
+}
@@ -1,18 +1,33 @@
-use std::fmt::Display;
-
+use chrono::{Duration, Utc};
 use color_eyre::Result;
-use firestore::{struct_path::path, FirestoreDb};
+use firestore::{struct_path::path, FirestoreDb, FirestoreTimestamp};
+use serenity::futures::{stream::BoxStream, StreamExt};
 use serenity::prelude::Context;
-use tracing::info;
+use tracing::{info, instrument};
 
-use crate::api::schema::{circle::Circle, coper::Coper, response::Response};
+use crate::api::schema::{
+    case::{Case, CaseCounter},
+    circle::Circle,
+    coper::Coper,
+    discord::Discord,
+    lockdown::Lockdown,
+    member::Member,
+    mute::Mute,
+    report::Report,
+    response::{Response, ResponsesType},
+};
 
 use super::super::settings::Settings;
 
+/// How many case numbers are tried before giving up on adding a case
+const CASE_ADD_ATTEMPTS: usize = 5;
+
+#[derive(Clone)]
 pub struct FSManager {
     pub client: FirestoreDb,
     #[allow(dead_code)]
     key_file: String,
+    strike_decay: Option<Duration>,
 }
 
 impl FSManager {
@@ -28,22 +43,24 @@ impl FSManager {
         Self {
             client,
             key_file: settings.firestore.key_filename.clone(),
+            strike_decay: (settings.moderation.strike_decay_days > 0)
+                .then(|| Duration::days(settings.moderation.strike_decay_days)),
         }
     }
 
-    /// Add a response into the database
+    /// Add or replace a response in the database
     /// # Arguments
     /// * `ctx` - The context of the command
     /// * `tipe` - The type of the response
     /// * `msg` - The message of the response
     /// # Errors
-    /// * If the database is unable to insert the response
-    /// * If the cache is unable to insert the response
-    pub async fn response_add(&self, ctx: Context, tipe: ResponseType, msg: &str) -> Result<()> {
+    /// * If the database is unable to save the response
+    /// * If the cache is unable to save the response
+    pub async fn response_add(&self, ctx: &Context, tipe: ResponsesType, msg: &str) -> Result<()> {
         let client = self.client.fluent();
         let res: Response = client
-            .insert()
-            .into("response")
+            .update()
+            .in_col("response")
             .document_id(tipe.to_string())
             .object(&Response {
                 type_field: tipe.to_string(),
@@ -63,17 +80,16 @@ impl FSManager {
     /// Delete a response from the database
     /// # Arguments
     /// * `ctx` - The context of the command
-    /// * `msg` - The message of the response
+    /// * `tipe` - The type of the response
     /// # Errors
     /// * If the database is unable to delete the response
     /// * If the cache is unable to delete the response
-    /// * If the response is not found
-    pub async fn response_delete(&self, ctx: Context, msg: &str) -> Result<()> {
+    pub async fn response_delete(&self, ctx: &Context, tipe: ResponsesType) -> Result<()> {
         let client = self.client.fluent();
         client
             .delete()
             .from("response")
-            .document_id(msg)
+            .document_id(tipe.to_string())
             .execute()
             .await?;
 
@@ -81,7 +97,7 @@ impl FSManager {
         let cache = data
             .get_mut::<Response>()
             .ok_or(eyre::eyre!("Unable to get cache"))?;
-        cache.remove(&msg.to_string());
+        cache.remove(&tipe.to_string());
 
         Ok(())
     }
@@ -244,26 +260,534 @@ impl FSManager {
         cache.insert(res_id, res);
         Ok(())
     }
-}
 
-pub enum ResponseType {
-    Error,
-    Basic,
-    Cors,
-    Default,
-    Opaque,
-    OpaqueDirect,
-}
+    /// Add a report into the database
+    /// # Arguments
+    /// * `ctx` - The context of the interaction
+    /// * `report` - The report to add
+    /// # Errors
+    /// * If the database is unable to insert the report
+    /// * If the cache is unable to insert the report
+    pub async fn report_add(&self, ctx: &Context, report: &Report) -> Result<()> {
+        let db = self.client.fluent();
+        let res: Report = db
+            .insert()
+            .into("report")
+            .document_id(&report.id)
+            .object(report)
+            .execute()
+            .await?;
+
+        let mut data = ctx.data.write().await;
+        let cache = data
+            .get_mut::<Report>()
+            .ok_or(eyre::eyre!("Unable to get cache"))?;
+        cache.insert(res.id.clone(), res);
+        Ok(())
+    }
+
+    /// Update the status and duplicates of a report in the database
+    /// # Arguments
+    /// * `ctx` - The context of the interaction
+    /// * `report` - The report with its new status
+    /// # Errors
+    /// * If the database is unable to update the report
+    /// * If the cache is unable to update the report
+    pub async fn report_update(&self, ctx: &Context, report: &Report) -> Result<()> {
+        let db = self.client.fluent();
+        let res: Report = db
+            .update()
+            .fields(vec![
+                path!(Report::status),
+                path!(Report::handled_by),
+                path!(Report::handled_on),
+                path!(Report::categories),
+                path!(Report::reporters),
+                path!(Report::details),
+                path!(Report::ongoing),
+                path!(Report::escalated),
+                path!(Report::thread),
+                path!(Report::card_message),
+            ])
+            .in_col("report")
+            .document_id(&report.id)
+            .object(report)
+            .execute()
+            .await?;
+
+        let mut data = ctx.data.write().await;
+        let cache = data
+            .get_mut::<Report>()
+            .ok_or(eyre::eyre!("Unable to get cache"))?;
+        // Closed reports no longer need to be kept around
+        match res.status.is_open() {
+            true => cache.insert(res.id.clone(), res),
+            false => cache.remove(&res.id),
+        };
+        Ok(())
+    }
+
+    /// Get the lockdown of a server, if it is locked down
+    /// # Arguments
+    /// * `guild_id` - The id of the server
+    /// # Errors
+    /// * If the database is unable to get the lockdown
+    pub async fn lockdown_get(&self, guild_id: &str) -> Result<Option<Lockdown>> {
+        let lockdown: Option<Lockdown> = self
+            .client
+            .fluent()
+            .select()
+            .by_id_in("lockdown")
+            .obj()
+            .one(guild_id)
+            .await?;
+        Ok(lockdown)
+    }
+
+    /// Save the lockdown of a server
+    /// # Arguments
+    /// * `lockdown` - The lockdown to save
+    /// # Errors
+    /// * If the database is unable to save the lockdown
+    pub async fn lockdown_set(&self, lockdown: &Lockdown) -> Result<()> {
+        let _: Lockdown = self
+            .client
+            .fluent()
+            .update()
+            .in_col("lockdown")
+            .document_id(&lockdown.id)
+            .object(lockdown)
+            .execute()
+            .await?;
+        Ok(())
+    }
+
+    /// Forget the lockdown of a server once it is lifted
+    /// # Arguments
+    /// * `guild_id` - The id of the server
+    /// # Errors
+    /// * If the database is unable to delete the lockdown
+    pub async fn lockdown_delete(&self, guild_id: &str) -> Result<()> {
+        self.client
+            .fluent()
+            .delete()
+            .from("lockdown")
+            .document_id(guild_id)
+            .execute()
+            .await?;
+        Ok(())
+    }
+
+    /// Add a strike to a member, creating their record if they have none
+    /// # Arguments
+    /// * `member_id` - The discord id of the member
+    /// # Errors
+    /// * If the database is unable to add or update the member
+    pub async fn member_strike(&self, member_id: &str) -> Result<Member> {
+        self.member_modify(member_id, |member| {
+            member.strikes += 1;
+            member.last_strike = FirestoreTimestamp(Utc::now());
+        })
+        .await
+    }
+
+    /// Change the record of a member, creating it if they have none
+    /// # Arguments
+    /// * `member_id` - The discord id of the member
+    /// * `modify` - The change to make to the member
+    /// # Errors
+    /// * If the database is unable to add or update the member
+    pub async fn member_modify(
+        &self,
+        member_id: &str,
+        modify: impl FnOnce(&mut Member) + Send,
+    ) -> Result<Member> {
+        let db = self.client.fluent();
+        let res: Option<Member> = db
+            .clone()
+            .select()
+            .by_id_in("member")
+            .obj()
+            .one(member_id)
+            .await?;
+        let mut member = match res {
+            Some(mut member) => {
+                self.decay(&mut member);
+                member
+            }
+            None => Member::new(member_id),
+        };
+        modify(&mut member);
 
-impl Display for ResponseType {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        match self {
-            Self::Error => write!(f, "error"),
-            Self::Basic => write!(f, "basic"),
-            Self::Cors => write!(f, "cors"),
-            Self::Default => write!(f, "default"),
-            Self::Opaque => write!(f, "opaque"),
-            Self::OpaqueDirect => write!(f, "opaquedirect"),
+        let res: Member = db
+            .update()
+            .in_col("member")
+            .document_id(member_id)
+            .object(&member)
+            .execute()
+            .await?;
+        Ok(res)
+    }
+
+    /// Get a member from the database, forgiving any strikes that decayed since it was last read
+    /// # Arguments
+    /// * `member_id` - The id of the member to get
+    /// # Errors
+    /// * If the database is unable to get the member
+    /// * If the database is unable to save the forgiven strikes
+    #[instrument(skip(self))]
+    pub async fn member_get(&self, member_id: &str) -> Result<Option<Member>> {
+        let db = self.client.fluent();
+        let res: Option<Member> = db
+            .clone()
+            .select()
+            .by_id_in("member")
+            .obj()
+            .one(member_id)
+            .await?;
+        let Some(mut member) = res else {
+            return Ok(None);
+        };
+        if self.decay(&mut member) {
+            self.member_save_decay(&member).await?;
         }
+        Ok(Some(member))
+    }
+
+    /// Get every member serving a temporary ban
+    /// # Errors
+    /// * If the database is unable to get the members
+    pub async fn member_banned(&self) -> Result<Vec<Member>> {
+        let members: BoxStream<Member> = self
+            .client
+            .fluent()
+            .select()
+            .from("member")
+            .filter(|q| q.field("bannedUntil").is_not_null())
+            .obj()
+            .stream_query()
+            .await?;
+        Ok(members.collect().await)
+    }
+
+    /// Forgive decayed strikes for every member in the database
+    /// # Errors
+    /// * If the database is unable to get the members
+    /// * If the database is unable to save the forgiven strikes
+    #[instrument(skip(self))]
+    pub async fn member_decay_all(&self) -> Result<usize> {
+        if self.strike_decay.is_none() {
+            return Ok(0);
+        }
+        let members: BoxStream<Member> = self
+            .client
+            .fluent()
+            .select()
+            .from("member")
+            .obj()
+            .stream_query()
+            .await?;
+        let members: Vec<Member> = members.collect().await;
+
+        let mut forgiven = 0;
+        for mut member in members {
+            if self.decay(&mut member) {
+                self.member_save_decay(&member).await?;
+                forgiven += 1;
+            }
+        }
+        Ok(forgiven)
+    }
+
+    /// Save a mute into the database, replacing any mute the member already had
+    /// # Arguments
+    /// * `mute` - The mute to save
+    /// # Errors
+    /// * If the database is unable to save the mute
+    pub async fn mute_set(&self, mute: &Mute) -> Result<()> {
+        let _: Mute = self
+            .client
+            .fluent()
+            .update()
+            .in_col("mute")
+            .document_id(&mute.id)
+            .object(mute)
+            .execute()
+            .await?;
+        Ok(())
+    }
+
+    /// Get the active mute of a member
+    /// # Arguments
+    /// * `member_id` - The discord id of the member
+    /// # Errors
+    /// * If the database is unable to get the mute
+    pub async fn mute_get(&self, member_id: &str) -> Result<Option<Mute>> {
+        let mute: Option<Mute> = self
+            .client
+            .fluent()
+            .select()
+            .by_id_in("mute")
+            .obj()
+            .one(member_id)
+            .await?;
+        Ok(mute)
+    }
+
+    /// Get every active mute
+    /// # Errors
+    /// * If the database is unable to get the mutes
+    pub async fn mute_all(&self) -> Result<Vec<Mute>> {
+        let mutes: BoxStream<Mute> = self
+            .client
+            .fluent()
+            .select()
+            .from("mute")
+            .obj()
+            .stream_query()
+            .await?;
+        Ok(mutes.collect().await)
+    }
+
+    /// Remove the mute of a member from the database
+    /// # Arguments
+    /// * `member_id` - The discord id of the member
+    /// # Errors
+    /// * If the database is unable to delete the mute
+    pub async fn mute_remove(&self, member_id: &str) -> Result<()> {
+        self.client
+            .fluent()
+            .delete()
+            .from("mute")
+            .document_id(member_id)
+            .execute()
+            .await?;
+        Ok(())
+    }
+
+    /// Give a case the next case number and add it into the database,
+    /// a case is only inserted when its number is still free so concurrent cases never share one
+    /// # Arguments
+    /// * `case` - The case to add
+    /// # Errors
+    /// * If the database is unable to get or update the case counter
+    /// * If the database is unable to insert the case
+    #[instrument(skip(self))]
+    pub async fn case_add(&self, mut case: Case) -> Result<Case> {
+        let db = self.client.fluent();
+        let counter: Option<CaseCounter> = db
+            .clone()
+            .select()
+            .by_id_in("counter")
+            .obj()
+            .one("case")
+            .await?;
+        let mut number = counter.map_or(0, |counter| counter.last);
+        let mut attempts = 0;
+        let res: Case = loop {
+            number += 1;
+            case.number = number;
+            case.id = number.to_string();
+            // Inserting fails when the document already exists, so a taken number is skipped
+            let res: firestore::FirestoreResult<Case> = db
+                .clone()
+                .insert()
+                .into("case")
+                .document_id(&case.id)
+                .object(&case)
+                .execute()
+                .await;
+            match res {
+                Ok(res) => break res,
+                Err(why) => {
+                    attempts += 1;
+                    if attempts >= CASE_ADD_ATTEMPTS || self.case_get(number).await?.is_none() {
+                        return Err(why.into());
+                    }
+                }
+            }
+        };
+
+        let counter = CaseCounter {
+            id: "case".to_string(),
+            last: number,
+        };
+        let _: CaseCounter = db
+            .update()
+            .in_col("counter")
+            .document_id(&counter.id)
+            .object(&counter)
+            .execute()
+            .await?;
+        Ok(res)
+    }
+
+    /// Get a case from the database
+    /// # Arguments
+    /// * `number` - The case number
+    /// # Errors
+    /// * If the database is unable to get the case
+    pub async fn case_get(&self, number: i64) -> Result<Option<Case>> {
+        let case: Option<Case> = self
+            .client
+            .fluent()
+            .select()
+            .by_id_in("case")
+            .obj()
+            .one(number.to_string())
+            .await?;
+        Ok(case)
+    }
+
+    /// Update the reason, details, log message and appeal of a case in the database
+    /// # Arguments
+    /// * `case` - The case with its changes
+    /// # Errors
+    /// * If the database is unable to update the case
+    pub async fn case_update(&self, case: &Case) -> Result<()> {
+        let _: Case = self
+            .client
+            .fluent()
+            .update()
+            .fields(vec![
+                path!(Case::reason),
+                path!(Case::details),
+                path!(Case::log_message),
+                path!(Case::appeal),
+            ])
+            .in_col("case")
+            .document_id(&case.id)
+            .object(case)
+            .execute()
+            .await?;
+        Ok(())
+    }
+
+    /// Get every case against a member, oldest first
+    /// # Arguments
+    /// * `member_id` - The discord id of the member
+    /// # Errors
+    /// * If the database is unable to get the cases
+    pub async fn case_history(&self, member_id: &str) -> Result<Vec<Case>> {
+        let cases: BoxStream<Case> = self
+            .client
+            .fluent()
+            .select()
+            .from("case")
+            .filter(|q| q.field("target").eq(member_id))
+            .obj()
+            .stream_query()
+            .await?;
+        let mut cases: Vec<Case> = cases.collect().await;
+        cases.sort_by_key(|case| case.number);
+        Ok(cases)
+    }
+
+    /// Get the linked profile of a discord account
+    /// # Arguments
+    /// * `member_id` - The discord id of the member
+    /// # Errors
+    /// * If the database is unable to get the profile
+    pub async fn discord_get(&self, member_id: &str) -> Result<Option<Discord>> {
+        let profile: Option<Discord> = self
+            .client
+            .fluent()
+            .select()
+            .by_id_in("discord")
+            .obj()
+            .one(member_id)
+            .await?;
+        Ok(profile)
+    }
+
+    /// Link a discord account to a member's profile, replacing any previous link
+    /// # Arguments
+    /// * `profile` - The profile to save
+    /// # Errors
+    /// * If the database is unable to save the profile
+    pub async fn discord_set(&self, profile: &Discord) -> Result<()> {
+        let _: Discord = self
+            .client
+            .fluent()
+            .update()
+            .in_col("discord")
+            .document_id(&profile.id)
+            .object(profile)
+            .execute()
+            .await?;
+        Ok(())
+    }
+
+    /// Get every profile that verified during a lockdown and still waits for the member role
+    /// # Errors
+    /// * If the database is unable to get the profiles
+    pub async fn discord_pending(&self) -> Result<Vec<Discord>> {
+        let profiles: BoxStream<Discord> = self
+            .client
+            .fluent()
+            .select()
+            .from("discord")
+            .filter(|q| q.field("pendingRole").eq(true))
+            .obj()
+            .stream_query()
+            .await?;
+        Ok(profiles.collect().await)
+    }
+
+    /// Get the profile a net-id is linked to, if any
+    /// # Arguments
+    /// * `net_id` - The university net-id, in lowercase
+    /// # Errors
+    /// * If the database is unable to get the profiles
+    pub async fn discord_by_net_id(&self, net_id: &str) -> Result<Option<Discord>> {
+        let mut profiles: BoxStream<Discord> = self
+            .client
+            .fluent()
+            .select()
+            .from("discord")
+            .filter(|q| q.field("netId").eq(net_id))
+            .obj()
+            .stream_query()
+            .await?;
+        Ok(profiles.next().await)
+    }
+
+    /// Get the coper score of a member
+    /// # Arguments
+    /// * `member_id` - The discord id of the member
+    /// # Errors
+    /// * If the database is unable to get the coper
+    pub async fn coper_get(&self, member_id: &str) -> Result<Option<Coper>> {
+        let coper: Option<Coper> = self
+            .client
+            .fluent()
+            .select()
+            .by_id_in("coper")
+            .obj()
+            .one(member_id)
+            .await?;
+        Ok(coper)
+    }
+
+    fn decay(&self, member: &mut Member) -> bool {
+        match self.strike_decay {
+            Some(every) => member.decay(every, Utc::now()),
+            None => false,
+        }
+    }
+
+    async fn member_save_decay(&self, member: &Member) -> Result<()> {
+        info!("{} decayed to {} strikes", member.id, member.strikes);
+        let _: Member = self
+            .client
+            .fluent()
+            .update()
+            .fields(vec![path!(Member::strikes), path!(Member::last_decay)])
+            .in_col("member")
+            .document_id(&member.id)
+            .object(member)
+            .execute()
+            .await?;
+        Ok(())
     }
 }
@@ -1,3 +1,9 @@
+pub mod audit;
+pub mod automod;
 pub mod circle;
+pub mod division;
 pub mod firestore;
-pub mod report;
\ No newline at end of file
+pub mod moderation;
+pub mod raid;
+pub mod report;
+pub mod verification;
@@ -0,0 +1,1104 @@
+use chrono::{Duration, Utc};
+use color_eyre::Result;
+use regex::Regex;
+use serenity::{
+    builder::CreateEmbed,
+    model::prelude::{
+        component::{ButtonStyle, InputTextStyle},
+        interaction::{
+            message_component::MessageComponentInteraction, modal::ModalSubmitInteraction,
+            InteractionResponseType,
+        },
+        ChannelId, GuildId, MessageId, RoleId, UserId,
+    },
+    prelude::Context,
+    utils::Color,
+};
+use tracing::{error, info, instrument, warn};
+
+use crate::{
+    api::{
+        bot::Bot,
+        schema::{
+            case::{Appeal, AppealStatus, Case, CaseAction},
+            member::Member,
+            mute::Mute,
+            response::{Response, ResponsesType},
+        },
+    },
+    managers::firestore::FSManager,
+    settings::{LadderAction, LadderStep, Settings},
+    util::{
+        modal::input_value,
+        roles::has_role,
+        text::truncate,
+        time::{bounded_minutes, format_duration},
+    },
+};
+
+/// Longest reason shown on a case, embed fields are limited to 1024 characters
+const CASE_REASON_LENGTH: usize = 1000;
+
+#[derive(Clone)]
+pub struct ModerationManager {
+    guild_id: GuildId,
+    mod_channel: Option<ChannelId>,
+    appeals_channel: Option<ChannelId>,
+    mute_role: Option<RoleId>,
+    ladder: Vec<LadderStep>,
+}
+
+impl ModerationManager {
+    #[must_use]
+    pub fn new(settings: &Settings) -> Self {
+        let mut ladder = settings.moderation.ladder.clone();
+        ladder.sort_by_key(|step| step.strikes);
+        Self {
+            guild_id: GuildId(settings.guild),
+            mod_channel: settings
+                .channels
+                .mod_field
+                .parse::<u64>()
+                .ok()
+                .map(ChannelId),
+            appeals_channel: settings.channels.appeals.parse::<u64>().ok().map(ChannelId),
+            mute_role: settings.roles.mute.parse::<u64>().ok().map(RoleId),
+            ladder,
+        }
+    }
+
+    /// Give a member a strike, let them know why and log it to the mod channel,
+    /// if the member reached a step of the escalation ladder it is applied as well
+    /// # Arguments
+    /// * `ctx` - The context of the interaction
+    /// * `bot` - The bot, used for the database
+    /// * `user` - The member to strike
+    /// * `moderator` - The staff member giving the strike
+    /// * `reason` - Why the strike was given
+    /// * `report` - The report that led to the strike
+    /// # Errors
+    /// * If the database is unable to update the member
+    /// * If the case cannot be opened
+    #[instrument(skip(self, ctx, bot))]
+    pub async fn strike(
+        &self,
+        ctx: &Context,
+        bot: &Bot,
+        user: UserId,
+        moderator: UserId,
+        reason: &str,
+        report: Option<&str>,
+    ) -> Result<Member> {
+        let member = bot
+            .firestore_manager
+            .member_strike(&user.to_string())
+            .await?;
+        info!("{} now has {} strikes", user, member.strikes);
+
+        let mut case = Case::new(
+            CaseAction::Strike,
+            &user.to_string(),
+            &moderator.to_string(),
+            reason,
+        );
+        case.details = Some(format!("Now has {} strikes", member.strikes));
+        case.report = report.map(str::to_string);
+        let case = self.number_case(bot, case).await?;
+        self.notify(ctx, user, ResponsesType::Strike, reason, None, case.number)
+            .await;
+        self.post_case(ctx, bot, case).await;
+
+        if let Some(step) = self.ladder_step(member.strikes) {
+            let reason = format!("Reached {} strikes: {}", member.strikes, reason);
+            // A strike from someone outranked must not become a kick or ban they could not give
+            if matches!(step.action, LadderAction::Kick | LadderAction::Ban) {
+                if let Err(why) = self.check_hierarchy(ctx, user, moderator).await {
+                    warn!(
+                        "Skipping the {:?} step of the ladder for {}: {:?}",
+                        step.action, user, why
+                    );
+                    return Ok(member);
+                }
+            }
+            let res = match step.action {
+                LadderAction::Mute => match step.duration_hours {
+                    Some(hours) => match hours.checked_mul(60).and_then(bounded_minutes) {
+                        Some(duration) => {
+                            self.mute(ctx, bot, user, moderator, Some(duration), &reason)
+                                .await
+                        }
+                        None => Err(eyre::eyre!("Invalid ladder mute of {} hours", hours)),
+                    },
+                    None => self.mute(ctx, bot, user, moderator, None, &reason).await,
+                },
+                LadderAction::Kick => self.kick(ctx, bot, user, moderator, &reason).await,
+                LadderAction::Ban => self.ban(ctx, bot, user, moderator, &reason, 0, None).await,
+            };
+            if let Err(why) = res {
+                error!("Unable to escalate the strike for {}: {:?}", user, why);
+            }
+        }
+
+        Ok(member)
+    }
+
+    /// Give a member the mute role, it is lifted again after `duration` if one is given
+    /// # Arguments
+    /// * `ctx` - The context of the interaction
+    /// * `bot` - The bot, used for the database
+    /// * `user` - The member to mute
+    /// * `moderator` - The staff member muting them
+    /// * `duration` - How long the mute lasts
+    /// * `reason` - Why the member was muted
+    /// # Errors
+    /// * If the mute role is not configured
+    /// * If the role cannot be given to the member
+    /// * If the case cannot be opened
+    #[instrument(skip(self, ctx, bot))]
+    pub async fn mute(
+        &self,
+        ctx: &Context,
+        bot: &Bot,
+        user: UserId,
+        moderator: UserId,
+        duration: Option<Duration>,
+        reason: &str,
+    ) -> Result<()> {
+        let mute_role = self
+            .mute_role
+            .ok_or(eyre::eyre!("The mute role is not configured"))?;
+        let now = Utc::now();
+        let expires_on = match duration {
+            Some(duration) => Some(
+                now.checked_add_signed(duration)
+                    .ok_or(eyre::eyre!("The mute duration is too long"))?,
+            ),
+            None => None,
+        };
+        let mute = Mute {
+            id: user.to_string(),
+            moderator: moderator.to_string(),
+            reason: reason.to_string(),
+            muted_on: now,
+            expires_on,
+        };
+        bot.firestore_manager.mute_set(&mute).await?;
+        ctx.http
+            .add_member_role(self.guild_id.0, user.0, mute_role.0, Some(reason))
+            .await?;
+        info!("Muted {}", user);
+        self.schedule_unmute(ctx, bot, &mute);
+
+        let length = duration.map_or_else(|| "until lifted".to_string(), format_duration);
+        let mut case = Case::new(
+            CaseAction::Mute,
+            &user.to_string(),
+            &moderator.to_string(),
+            reason,
+        );
+        case.details = Some(format!("Muted for {length}"));
+        let case = self.number_case(bot, case).await?;
+        self.notify(
+            ctx,
+            user,
+            ResponsesType::Mute,
+            reason,
+            duration,
+            case.number,
+        )
+        .await;
+        self.post_case(ctx, bot, case).await;
+        Ok(())
+    }
+
+    /// Lift the mute of a member before it expires
+    /// # Arguments
+    /// * `ctx` - The context of the interaction
+    /// * `bot` - The bot, used for the database
+    /// * `user` - The member to unmute
+    /// * `moderator` - The staff member lifting the mute
+    /// # Errors
+    /// * If the mute role is not configured
+    /// * If the role cannot be removed from the member
+    /// * If the database is unable to remove the mute
+    /// * If the unmute cannot be logged
+    #[instrument(skip(self, ctx, bot))]
+    pub async fn unmute(
+        &self,
+        ctx: &Context,
+        bot: &Bot,
+        user: UserId,
+        moderator: UserId,
+    ) -> Result<()> {
+        self.lift_mute(ctx, &bot.firestore_manager, user, "Unmuted by staff")
+            .await?;
+
+        let embed = CreateEmbed::default()
+            .title("Unmute")
+            .color(Color::DARK_GREEN)
+            .field("**Member**", format!("<@{user}>"), true)
+            .field("**Moderator**", format!("<@{moderator}>"), true)
+            .clone();
+        self.log(ctx, embed).await?;
+        Ok(())
+    }
+
+    /// Schedule the unmute of every stored mute, mutes that expired while the bot
+    /// was offline are lifted right away
+    /// # Arguments
+    /// * `ctx` - The context of the bot
+    /// * `bot` - The bot, used for the database
+    /// # Errors
+    /// * If the database is unable to get the mutes
+    #[instrument(skip(self, ctx, bot))]
+    pub async fn reschedule_mutes(&self, ctx: &Context, bot: &Bot) -> Result<usize> {
+        let mutes = bot.firestore_manager.mute_all().await?;
+        for mute in &mutes {
+            self.schedule_unmute(ctx, bot, mute);
+        }
+        Ok(mutes.len())
+    }
+
+    /// Give the mute role back to a member who left and rejoined while muted
+    /// # Arguments
+    /// * `ctx` - The context of the event
+    /// * `bot` - The bot, used for the database
+    /// * `user` - The member who joined
+    /// # Errors
+    /// * If the database is unable to get the mute
+    /// * If the role cannot be given to the member
+    #[instrument(skip(self, ctx, bot))]
+    pub async fn reapply_mute(&self, ctx: &Context, bot: &Bot, user: UserId) -> Result<()> {
+        let Some(mute) = bot.firestore_manager.mute_get(&user.to_string()).await? else {
+            return Ok(());
+        };
+        if mute.is_expired(Utc::now()) {
+            return Ok(());
+        }
+
+        let mute_role = self
+            .mute_role
+            .ok_or(eyre::eyre!("The mute role is not configured"))?;
+        ctx.http
+            .add_member_role(
+                self.guild_id.0,
+                user.0,
+                mute_role.0,
+                Some("Rejoined while muted"),
+            )
+            .await?;
+        info!("Reapplied the mute on {}", user);
+        Ok(())
+    }
+
+    /// Lift a mute once it expires, the mute is checked again when the timer fires
+    /// since it may have been lifted or replaced in the meantime
+    fn schedule_unmute(&self, ctx: &Context, bot: &Bot, mute: &Mute) {
+        let Some(expires_on) = mute.expires_on else {
+            return;
+        };
+        let Ok(user) = mute.id.parse::<u64>().map(UserId) else {
+            warn!("Stored mute has an invalid member id {}", mute.id);
+            return;
+        };
+
+        let wait = (expires_on - Utc::now()).to_std().unwrap_or_default();
+        let manager = self.clone();
+        let firestore_manager = bot.firestore_manager.clone();
+        let ctx = ctx.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(wait).await;
+            if let Err(why) = manager.expire_mute(&ctx, &firestore_manager, user).await {
+                error!("Unable to lift the mute on {}: {:?}", user, why);
+            }
+        });
+    }
+
+    #[instrument(skip(self, ctx, firestore_manager))]
+    async fn expire_mute(
+        &self,
+        ctx: &Context,
+        firestore_manager: &FSManager,
+        user: UserId,
+    ) -> Result<()> {
+        let Some(mute) = firestore_manager.mute_get(&user.to_string()).await? else {
+            return Ok(());
+        };
+        if !mute.is_expired(Utc::now()) {
+            return Ok(());
+        }
+        self.lift_mute(ctx, firestore_manager, user, "Mute expired")
+            .await?;
+
+        let embed = CreateEmbed::default()
+            .title("Mute Expired")
+            .color(Color::DARK_GREEN)
+            .field("**Member**", format!("<@{user}>"), true)
+            .clone();
+        self.log(ctx, embed).await?;
+        Ok(())
+    }
+
+    async fn lift_mute(
+        &self,
+        ctx: &Context,
+        firestore_manager: &FSManager,
+        user: UserId,
+        reason: &str,
+    ) -> Result<()> {
+        let mute_role = self
+            .mute_role
+            .ok_or(eyre::eyre!("The mute role is not configured"))?;
+        // Members who left no longer have the role, the mute is still removed
+        if let Err(why) = ctx
+            .http
+            .remove_member_role(self.guild_id.0, user.0, mute_role.0, Some(reason))
+            .await
+        {
+            warn!("Unable to remove the mute role from {}: {:?}", user, why);
+        }
+        firestore_manager.mute_remove(&user.to_string()).await?;
+        info!("Lifted the mute on {}", user);
+        Ok(())
+    }
+
+    /// Kick a member from the server, they are messaged before being kicked
+    /// since the bot can no longer DM them afterwards, a failed kick is noted on its case
+    /// # Arguments
+    /// * `ctx` - The context of the interaction
+    /// * `bot` - The bot, used for the database
+    /// * `user` - The member to kick
+    /// * `moderator` - The staff member kicking them
+    /// * `reason` - Why the member was kicked
+    /// # Errors
+    /// * If the member cannot be kicked
+    /// * If the database is unable to record the kick
+    /// * If the case cannot be opened
+    #[instrument(skip(self, ctx, bot))]
+    pub async fn kick(
+        &self,
+        ctx: &Context,
+        bot: &Bot,
+        user: UserId,
+        moderator: UserId,
+        reason: &str,
+    ) -> Result<()> {
+        let case = Case::new(
+            CaseAction::Kick,
+            &user.to_string(),
+            &moderator.to_string(),
+            reason,
+        );
+        let mut case = self.number_case(bot, case).await?;
+        self.notify(ctx, user, ResponsesType::Kick, reason, None, case.number)
+            .await;
+        if let Err(why) = self
+            .guild_id
+            .kick_with_reason(&ctx.http, user, reason)
+            .await
+        {
+            case.details = Some(format!("The kick failed: {why}"));
+            self.post_case(ctx, bot, case).await;
+            return Err(why.into());
+        }
+        info!("Kicked {}", user);
+
+        self.post_case(ctx, bot, case).await;
+        bot.firestore_manager
+            .member_modify(&user.to_string(), |member| member.kicks += 1)
+            .await?;
+        Ok(())
+    }
+
+    /// Ban a member from the server, they are messaged before being banned
+    /// since the bot can no longer DM them afterwards, a failed ban is noted on its case
+    /// # Arguments
+    /// * `ctx` - The context of the interaction
+    /// * `bot` - The bot, used for the database
+    /// * `user` - The member to ban
+    /// * `moderator` - The staff member banning them
+    /// * `reason` - Why the member was banned
+    /// * `delete_days` - How many days of the member's messages to delete, at most 7
+    /// * `duration` - How long the ban lasts, `None` bans them for good
+    /// # Errors
+    /// * If the member cannot be banned
+    /// * If the database is unable to record the ban
+    /// * If the case cannot be opened
+    #[instrument(skip(self, ctx, bot))]
+    #[allow(clippy::too_many_arguments)]
+    pub async fn ban(
+        &self,
+        ctx: &Context,
+        bot: &Bot,
+        user: UserId,
+        moderator: UserId,
+        reason: &str,
+        delete_days: u8,
+        duration: Option<Duration>,
+    ) -> Result<()> {
+        let banned_until = match duration {
+            Some(duration) => Some(
+                Utc::now()
+                    .checked_add_signed(duration)
+                    .ok_or(eyre::eyre!("The ban duration is too long"))?,
+            ),
+            None => None,
+        };
+        let mut case = Case::new(
+            CaseAction::Ban,
+            &user.to_string(),
+            &moderator.to_string(),
+            reason,
+        );
+        case.details = Some(match duration {
+            Some(duration) => format!("Banned for {}", format_duration(duration)),
+            None => "Permanent ban".to_string(),
+        });
+        let mut case = self.number_case(bot, case).await?;
+        self.notify(ctx, user, ResponsesType::Ban, reason, duration, case.number)
+            .await;
+        if let Err(why) = self
+            .guild_id
+            .ban_with_reason(&ctx.http, user, delete_days.min(7), reason)
+            .await
+        {
+            case.details = case
+                .details
+                .map(|details| format!("{details}\nThe ban failed: {why}"));
+            self.post_case(ctx, bot, case).await;
+            return Err(why.into());
+        }
+        info!("Banned {}", user);
+
+        self.post_case(ctx, bot, case).await;
+        let member = bot
+            .firestore_manager
+            .member_modify(&user.to_string(), |member| {
+                member.bans += 1;
+                member.banned_until = banned_until;
+            })
+            .await?;
+        self.schedule_unban(ctx, bot, &member);
+        Ok(())
+    }
+
+    /// Schedule the unban of every temporary ban, bans that expired while the bot
+    /// was offline are lifted right away
+    /// # Arguments
+    /// * `ctx` - The context of the bot
+    /// * `bot` - The bot, used for the database
+    /// # Errors
+    /// * If the database is unable to get the banned members
+    #[instrument(skip(self, ctx, bot))]
+    pub async fn reschedule_bans(&self, ctx: &Context, bot: &Bot) -> Result<usize> {
+        let members = bot.firestore_manager.member_banned().await?;
+        for member in &members {
+            self.schedule_unban(ctx, bot, member);
+        }
+        Ok(members.len())
+    }
+
+    /// Make sure a moderator is allowed to act on a member, nobody can act on the
+    /// server owner or on members whose top role is at or above their own
+    /// # Arguments
+    /// * `ctx` - The context of the interaction
+    /// * `user` - The member being moderated
+    /// * `moderator` - The staff member acting on them
+    /// # Errors
+    /// * If the moderator is not allowed to act on the member
+    /// * If the server or its members cannot be fetched
+    #[instrument(skip(self, ctx))]
+    pub async fn check_hierarchy(
+        &self,
+        ctx: &Context,
+        user: UserId,
+        moderator: UserId,
+    ) -> Result<()> {
+        let guild = self.guild_id.to_partial_guild(&ctx.http).await?;
+        if user == guild.owner_id {
+            return Err(eyre::eyre!("The server owner cannot be moderated"));
+        }
+        if moderator == guild.owner_id {
+            return Ok(());
+        }
+        // Users who already left the server have no roles to compare
+        let Ok(target) = self.guild_id.member(ctx, user).await else {
+            return Ok(());
+        };
+        let invoker = self.guild_id.member(ctx, moderator).await?;
+
+        let top_role = |roles: &[RoleId]| {
+            roles
+                .iter()
+                .filter_map(|role| guild.roles.get(role))
+                .map(|role| role.position)
+                .max()
+                .unwrap_or(0)
+        };
+        if top_role(&target.roles) >= top_role(&invoker.roles) {
+            return Err(eyre::eyre!(
+                "{} has a role at or above yours",
+                target.user.tag()
+            ));
+        }
+        Ok(())
+    }
+
+    /// Lift a temporary ban once it expires, the ban is checked again when the timer fires
+    fn schedule_unban(&self, ctx: &Context, bot: &Bot, member: &Member) {
+        let Some(banned_until) = member.banned_until else {
+            return;
+        };
+        let Ok(user) = member.id.parse::<u64>().map(UserId) else {
+            warn!("Stored member has an invalid id {}", member.id);
+            return;
+        };
+
+        let wait = (banned_until - Utc::now()).to_std().unwrap_or_default();
+        let manager = self.clone();
+        let firestore_manager = bot.firestore_manager.clone();
+        let ctx = ctx.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(wait).await;
+            if let Err(why) = manager.expire_ban(&ctx, &firestore_manager, user).await {
+                error!("Unable to lift the ban on {}: {:?}", user, why);
+            }
+        });
+    }
+
+    #[instrument(skip(self, ctx, firestore_manager))]
+    async fn expire_ban(
+        &self,
+        ctx: &Context,
+        firestore_manager: &FSManager,
+        user: UserId,
+    ) -> Result<()> {
+        let Some(member) = firestore_manager.member_get(&user.to_string()).await? else {
+            return Ok(());
+        };
+        match member.banned_until {
+            Some(banned_until) if banned_until <= Utc::now() => {}
+            _ => return Ok(()),
+        }
+
+        self.guild_id.unban(&ctx.http, user).await?;
+        firestore_manager
+            .member_modify(&user.to_string(), |member| member.banned_until = None)
+            .await?;
+        info!("Lifted the ban on {}", user);
+
+        let embed = CreateEmbed::default()
+            .title("Ban Expired")
+            .color(Color::DARK_GREEN)
+            .field("**Member**", format!("<@{user}>"), true)
+            .clone();
+        self.log(ctx, embed).await?;
+        Ok(())
+    }
+
+    /// Number a case, save it and post it to the mod channel
+    /// # Arguments
+    /// * `ctx` - The context of the interaction
+    /// * `bot` - The bot, used for the database
+    /// * `case` - The case to open
+    /// # Errors
+    /// * If the database is unable to save the case
+    #[instrument(skip(self, ctx, bot))]
+    pub async fn open_case(&self, ctx: &Context, bot: &Bot, case: Case) -> Result<Case> {
+        let case = self.number_case(bot, case).await?;
+        Ok(self.post_case(ctx, bot, case).await)
+    }
+
+    /// Number a case and save it, punishments are numbered before the member is told
+    /// so the case can be appealed from their DMs
+    async fn number_case(&self, bot: &Bot, case: Case) -> Result<Case> {
+        let case = bot.firestore_manager.case_add(case).await?;
+        info!("Opened case {} against {}", case.number, case.target);
+        Ok(case)
+    }
+
+    /// Post a numbered case to the mod channel and remember its message,
+    /// the case is already saved so failing to post it does not stop the action
+    async fn post_case(&self, ctx: &Context, bot: &Bot, mut case: Case) -> Case {
+        let message = match self.log(ctx, case_embed(&case)).await {
+            Ok(message) => message,
+            Err(why) => {
+                warn!("Unable to post case {}: {:?}", case.number, why);
+                return case;
+            }
+        };
+        case.log_message = Some(message.to_string());
+        if let Err(why) = bot.firestore_manager.case_update(&case).await {
+            warn!(
+                "Unable to save the message of case {}: {:?}",
+                case.number, why
+            );
+        }
+        case
+    }
+
+    /// Change the reason of a case and update its message in the mod channel
+    /// # Arguments
+    /// * `ctx` - The context of the interaction
+    /// * `bot` - The bot, used for the database
+    /// * `number` - The case number
+    /// * `reason` - The new reason
+    /// # Errors
+    /// * If the case does not exist
+    /// * If the database is unable to update the case
+    #[instrument(skip(self, ctx, bot))]
+    pub async fn amend_case(
+        &self,
+        ctx: &Context,
+        bot: &Bot,
+        number: i64,
+        reason: &str,
+    ) -> Result<Case> {
+        let mut case = bot
+            .firestore_manager
+            .case_get(number)
+            .await?
+            .ok_or(eyre::eyre!("Case {} does not exist", number))?;
+        case.reason = reason.to_string();
+        bot.firestore_manager.case_update(&case).await?;
+        self.refresh_case_message(ctx, &case).await;
+        Ok(case)
+    }
+
+    /// Handle the appeal buttons, either a member opening an appeal from their DMs
+    /// or a director deciding on it
+    /// # Arguments
+    /// * `ctx` - The context of the interaction
+    /// * `int` - The button interaction, with a custom id of `appeal/<open|accept|deny>/<case>`
+    /// * `bot` - The bot, used for settings and the database
+    /// # Returns
+    /// The updated appeal card once a director decided, `None` when a modal was opened
+    /// # Errors
+    /// * If the member is not allowed to use the button
+    /// * If the case does not exist or was already appealed
+    /// * If the decision cannot be applied
+    #[instrument(skip(self, ctx, int, bot))]
+    pub async fn handle_appeal_button(
+        &self,
+        ctx: &Context,
+        int: &MessageComponentInteraction,
+        bot: &Bot,
+    ) -> Result<Option<CreateEmbed>> {
+        let (action, number) = parse_appeal_id(&int.data.custom_id)?;
+        let case = bot
+            .firestore_manager
+            .case_get(number)
+            .await?
+            .ok_or(eyre::eyre!("Case {} does not exist", number))?;
+
+        match action.as_str() {
+            "open" => {
+                check_appealable(&case, int.user.id)?;
+                int.create_interaction_response(&ctx.http, |r| {
+                    r.kind(InteractionResponseType::Modal)
+                        .interaction_response_data(|d| {
+                            d.custom_id(format!("appeal/submit/{number}"))
+                                .title(format!("Appeal case #{number}"))
+                                .components(|c| {
+                                    c.create_action_row(|row| {
+                                        row.create_input_text(|i| {
+                                            i.custom_id("appeal")
+                                                .label("Why should this be reversed?")
+                                                .style(InputTextStyle::Paragraph)
+                                                .max_length(1000)
+                                                .required(true)
+                                        })
+                                    })
+                                })
+                        })
+                })
+                .await?;
+                Ok(None)
+            }
+            "accept" | "deny" => {
+                if !has_role(int.member.as_ref(), &bot.settings.roles.director) {
+                    return Err(eyre::eyre!("Only directors can decide on appeals"));
+                }
+                let status = match action.as_str() {
+                    "accept" => AppealStatus::Accepted,
+                    _ => AppealStatus::Denied,
+                };
+                let case = self
+                    .decide_appeal(ctx, bot, case, status, int.user.id)
+                    .await?;
+                Ok(Some(appeal_embed(&case)))
+            }
+            _ => Err(eyre::eyre!("Unable to get action")),
+        }
+    }
+
+    /// Store an appeal against its case and send it to the directors
+    /// # Arguments
+    /// * `ctx` - The context of the interaction
+    /// * `int` - The modal interaction, with a custom id of `appeal/submit/<case>`
+    /// * `bot` - The bot, used for the database
+    /// # Errors
+    /// * If the case cannot be appealed by the member
+    /// * If the appeals channel is not configured
+    /// * If the appeal cannot be saved or sent
+    #[instrument(skip(self, ctx, int, bot))]
+    pub async fn handle_appeal_modal(
+        &self,
+        ctx: &Context,
+        int: &ModalSubmitInteraction,
+        bot: &Bot,
+    ) -> Result<String> {
+        let (_, number) = parse_appeal_id(&int.data.custom_id)?;
+        let message =
+            input_value(&int.data.components, "appeal").ok_or(eyre::eyre!("No appeal provided"))?;
+        let appeals_channel = self
+            .appeals_channel
+            .ok_or(eyre::eyre!("Appeals are not being accepted right now"))?;
+
+        let mut case = bot
+            .firestore_manager
+            .case_get(number)
+            .await?
+            .ok_or(eyre::eyre!("Case {} does not exist", number))?;
+        check_appealable(&case, int.user.id)?;
+        case.appeal = Some(Appeal {
+            message,
+            status: AppealStatus::Pending,
+            submitted_on: Utc::now(),
+            handled_by: None,
+            handled_on: None,
+        });
+        bot.firestore_manager.case_update(&case).await?;
+        info!("Case {} has been appealed", number);
+
+        let embed = appeal_embed(&case);
+        appeals_channel
+            .send_message(&ctx.http, |m| {
+                m.set_embed(embed).components(|c| {
+                    c.create_action_row(|row| {
+                        row.create_button(|b| {
+                            b.custom_id(format!("appeal/accept/{number}"))
+                                .label("Accept")
+                                .style(ButtonStyle::Success)
+                        })
+                        .create_button(|b| {
+                            b.custom_id(format!("appeal/deny/{number}"))
+                                .label("Deny")
+                                .style(ButtonStyle::Danger)
+                        })
+                    })
+                })
+            })
+            .await?;
+        self.refresh_case_message(ctx, &case).await;
+
+        Ok(format!(
+            "Your appeal for case #{number} has been sent to the directors"
+        ))
+    }
+
+    /// Accept or deny an appeal, accepted appeals reverse the action of the case
+    async fn decide_appeal(
+        &self,
+        ctx: &Context,
+        bot: &Bot,
+        mut case: Case,
+        status: AppealStatus,
+        director: UserId,
+    ) -> Result<Case> {
+        let appeal = case
+            .appeal
+            .as_mut()
+            .ok_or(eyre::eyre!("Case {} has not been appealed", case.number))?;
+        if appeal.status != AppealStatus::Pending {
+            return Err(eyre::eyre!(
+                "The appeal for case {} was already {}",
+                case.number,
+                appeal.status
+            ));
+        }
+        appeal.status = status;
+        appeal.handled_by = Some(director.to_string());
+        appeal.handled_on = Some(Utc::now());
+
+        let user = UserId(case.target.parse::<u64>()?);
+        if status == AppealStatus::Accepted {
+            self.reverse(ctx, bot, &case, user).await?;
+        }
+        bot.firestore_manager.case_update(&case).await?;
+        info!("The appeal for case {} was {}", case.number, status);
+        self.refresh_case_message(ctx, &case).await;
+
+        let outcome = match status {
+            AppealStatus::Accepted => "accepted and the action has been reversed",
+            _ => "denied",
+        };
+        let content = format!(
+            "Your appeal for case #{} ({}) has been {}.",
+            case.number, case.action, outcome
+        );
+        if let Err(why) = self.dm(ctx, user, content, None).await {
+            warn!("Unable to DM {} about their appeal: {:?}", user, why);
+        }
+        Ok(case)
+    }
+
+    /// Undo the action of a case after an accepted appeal
+    async fn reverse(&self, ctx: &Context, bot: &Bot, case: &Case, user: UserId) -> Result<()> {
+        match case.action {
+            CaseAction::Strike => {
+                bot.firestore_manager
+                    .member_modify(&case.target, |member| {
+                        member.strikes = (member.strikes - 1).max(0);
+                    })
+                    .await?;
+            }
+            CaseAction::Mute => {
+                if bot
+                    .firestore_manager
+                    .mute_get(&case.target)
+                    .await?
+                    .is_some()
+                {
+                    self.lift_mute(ctx, &bot.firestore_manager, user, "Appeal accepted")
+                        .await?;
+                }
+            }
+            CaseAction::Ban => {
+                self.guild_id.unban(&ctx.http, user).await?;
+                bot.firestore_manager
+                    .member_modify(&case.target, |member| member.banned_until = None)
+                    .await?;
+            }
+            // Kicked members are free to rejoin, there is nothing to undo
+            CaseAction::Kick => {}
+            CaseAction::ReportResolved | CaseAction::ReportDismissed => {
+                return Err(eyre::eyre!("Report resolutions cannot be appealed"));
+            }
+        }
+        Ok(())
+    }
+
+    /// Update the copy of a case in the mod channel, the stored case is what counts
+    /// so failing to edit the message is only logged
+    async fn refresh_case_message(&self, ctx: &Context, case: &Case) {
+        let message = case
+            .log_message
+            .as_ref()
+            .and_then(|id| id.parse::<u64>().ok())
+            .map(MessageId);
+        if let (Some(mod_channel), Some(message)) = (self.mod_channel, message) {
+            let embed = case_embed(case);
+            if let Err(why) = mod_channel
+                .edit_message(&ctx.http, message, |m| m.set_embed(embed))
+                .await
+            {
+                warn!(
+                    "Unable to edit the message of case {}: {:?}",
+                    case.number, why
+                );
+            }
+        }
+    }
+
+    /// Get the highest step of the ladder a member with `strikes` strikes has reached
+    fn ladder_step(&self, strikes: i64) -> Option<&LadderStep> {
+        self.ladder
+            .iter()
+            .rev()
+            .find(|step| step.strikes <= strikes)
+    }
+
+    /// DM a member the configured response for an action with a button to appeal it,
+    /// this is best effort since members can have their DMs closed
+    #[instrument(skip(self, ctx))]
+    async fn notify(
+        &self,
+        ctx: &Context,
+        user: UserId,
+        action: ResponsesType,
+        reason: &str,
+        duration: Option<Duration>,
+        case: i64,
+    ) {
+        let duration = duration.map(format_duration);
+        let mut content = match self.get_response(ctx, action).await {
+            Some(response) => response.render(&format!("<@{user}>"), reason, duration.as_deref()),
+            None => {
+                let mut content = format!(
+                    "You have received a {action} from the ACM moderators.\n**Reason:** {reason}"
+                );
+                if let Some(duration) = duration {
+                    content.push_str(&format!("\n**Duration:** {duration}"));
+                }
+                content
+            }
+        };
+        content.push_str(&format!("\n**Case:** #{case}"));
+
+        let appeal = self.appeals_channel.map(|_| case);
+        if let Err(why) = self.dm(ctx, user, content, appeal).await {
+            warn!("Unable to DM {} about their {}: {:?}", user, action, why);
+        }
+    }
+
+    /// DM a member, with an appeal button for `appeal` if one is given
+    async fn dm(
+        &self,
+        ctx: &Context,
+        user: UserId,
+        content: String,
+        appeal: Option<i64>,
+    ) -> Result<()> {
+        let dm = user.create_dm_channel(&ctx.http).await?;
+        dm.send_message(&ctx.http, |m| {
+            m.content(content);
+            if let Some(case) = appeal {
+                m.components(|c| {
+                    c.create_action_row(|row| {
+                        row.create_button(|b| {
+                            b.custom_id(format!("appeal/open/{case}"))
+                                .label("Appeal")
+                                .style(ButtonStyle::Secondary)
+                        })
+                    })
+                });
+            }
+            m
+        })
+        .await?;
+        Ok(())
+    }
+
+    /// Get the configured response for an action from the cache
+    async fn get_response(&self, ctx: &Context, action: ResponsesType) -> Option<Response> {
+        let data = ctx.data.read().await;
+        data.get::<Response>()?.get(&action.to_string()).cloned()
+    }
+
+    /// Post a moderation action to the mod channel
+    #[instrument(skip(self, ctx, embed))]
+    pub async fn log(&self, ctx: &Context, embed: CreateEmbed) -> Result<MessageId> {
+        let mod_channel = self
+            .mod_channel
+            .ok_or(eyre::eyre!("The mod channel is not configured"))?;
+        let message = mod_channel
+            .send_message(&ctx.http, |m| m.set_embed(embed))
+            .await?;
+        Ok(message.id)
+    }
+
+    /// Post an alert that needs the attention of staff to the mod channel
+    #[instrument(skip(self, ctx, content, embed))]
+    pub async fn alert(&self, ctx: &Context, content: String, embed: CreateEmbed) -> Result<()> {
+        let mod_channel = self
+            .mod_channel
+            .ok_or(eyre::eyre!("The mod channel is not configured"))?;
+        mod_channel
+            .send_message(&ctx.http, |m| m.content(content).set_embed(embed))
+            .await?;
+        Ok(())
+    }
+}
+
+/// Build the embed showing a case
+pub fn case_embed(case: &Case) -> CreateEmbed {
+    let color = match case.action {
+        CaseAction::Strike | CaseAction::Mute => Color::ORANGE,
+        CaseAction::Kick => Color::RED,
+        CaseAction::Ban => Color::DARK_RED,
+        CaseAction::ReportResolved | CaseAction::ReportDismissed => Color::DARK_GREEN,
+    };
+    let mut embed = CreateEmbed::default();
+    embed
+        .title(format!("Case #{} | {}", case.number, case.action))
+        .color(color)
+        .field("**Member**", format!("<@{}>", case.target), true)
+        .field("**Moderator**", format!("<@{}>", case.moderator), true)
+        .field(
+            "**Reason**",
+            truncate(&case.reason, CASE_REASON_LENGTH),
+            false,
+        )
+        .timestamp(case.created_on.to_rfc3339());
+    if let Some(details) = &case.details {
+        embed.field("**Details**", details, true);
+    }
+    if let Some(report) = &case.report {
+        embed.field("**Report**", report, true);
+    }
+    if let Some(appeal) = &case.appeal {
+        embed.field("**Appeal**", appeal.status, true);
+    }
+    embed
+}
+
+/// Build the embed directors see for an appeal
+fn appeal_embed(case: &Case) -> CreateEmbed {
+    let mut embed = CreateEmbed::default();
+    embed
+        .title(format!(
+            "Appeal for case #{} | {}",
+            case.number, case.action
+        ))
+        .color(Color::BLUE)
+        .field("**Member**", format!("<@{}>", case.target), true)
+        .field("**Moderator**", format!("<@{}>", case.moderator), true)
+        .field(
+            "**Reason**",
+            truncate(&case.reason, CASE_REASON_LENGTH),
+            false,
+        );
+    if let Some(appeal) = &case.appeal {
+        embed
+            .field(
+                "**Appeal**",
+                truncate(&appeal.message, CASE_REASON_LENGTH),
+                false,
+            )
+            .field("**Status**", appeal.status, true);
+        if let Some(director) = &appeal.handled_by {
+            embed.field("**Decided By**", format!("<@{director}>"), true);
+        }
+        embed.color(match appeal.status {
+            AppealStatus::Pending => Color::BLUE,
+            AppealStatus::Accepted => Color::DARK_GREEN,
+            AppealStatus::Denied => Color::RED,
+        });
+    }
+    embed
+}
+
+/// Make sure a member can appeal a case, only punishments against them that
+/// were not appealed before can be
+fn check_appealable(case: &Case, user: UserId) -> Result<()> {
+    if case.target != user.to_string() || !case.action.is_punishment() {
+        return Err(eyre::eyre!("You cannot appeal case {}", case.number));
+    }
+    if case.appeal.is_some() {
+        return Err(eyre::eyre!(
+            "Case {} has already been appealed",
+            case.number
+        ));
+    }
+    Ok(())
+}
+
+/// Get the action and case number from an `appeal/<action>/<case>` custom id
+fn parse_appeal_id(custom_id: &str) -> Result<(String, i64)> {
+    let reg = Regex::new(r"appeal/([^/]+)/(\d+)")?;
+    let matches = reg
+        .captures(custom_id)
+        .ok_or(eyre::eyre!("Unable to get matches"))?;
+    let action = matches
+        .get(1)
+        .ok_or(eyre::eyre!("Unable to get action"))?
+        .as_str()
+        .to_string();
+    let number = matches
+        .get(2)
+        .ok_or(eyre::eyre!("Unable to get case number"))?
+        .as_str()
+        .parse::<i64>()?;
+    Ok((action, number))
+}
@@ -0,0 +1,271 @@
+use std::collections::VecDeque;
+use std::sync::Arc;
+
+use chrono::{DateTime, Duration, TimeZone, Utc};
+use color_eyre::Result;
+use serenity::builder::CreateEmbed;
+use serenity::client::Context;
+use serenity::model::prelude::{GuildId, Member, UserId, VerificationLevel};
+use serenity::prelude::Mutex;
+use serenity::utils::Color;
+use tracing::{info, instrument, warn};
+
+use crate::api::bot::Bot;
+use crate::api::schema::lockdown::Lockdown;
+use crate::settings::Settings;
+
+/// The longest window joins are counted in
+const MAX_WINDOW_SECS: i64 = 60 * 60 * 24;
+/// The oldest an account can be to still count as new
+const MAX_NEW_ACCOUNT_DAYS: i64 = 365;
+
+/// A member that joined recently
+struct Join {
+    joined_on: DateTime<Utc>,
+    new_account: bool,
+}
+
+#[derive(Default)]
+struct RaidState {
+    joins: VecDeque<Join>,
+    last_alert: Option<DateTime<Utc>>,
+    lockdown: Option<Lockdown>,
+}
+
+#[derive(Clone)]
+pub struct RaidManager {
+    guild_id: GuildId,
+    enabled: bool,
+    max_joins: Option<usize>,
+    max_new_accounts: Option<usize>,
+    new_account_age: Duration,
+    window: Duration,
+    auto_lockdown: bool,
+    staff_role: String,
+    state: Arc<Mutex<RaidState>>,
+}
+
+impl RaidManager {
+    #[must_use]
+    pub fn new(settings: &Settings) -> Self {
+        let raid = &settings.raid;
+        Self {
+            guild_id: GuildId(settings.guild),
+            enabled: raid.enabled,
+            max_joins: (raid.max_joins > 0).then_some(raid.max_joins),
+            max_new_accounts: (raid.max_new_accounts > 0).then_some(raid.max_new_accounts),
+            new_account_age: Duration::days(raid.new_account_days.clamp(0, MAX_NEW_ACCOUNT_DAYS)),
+            window: Duration::seconds(raid.window_secs.clamp(1, MAX_WINDOW_SECS)),
+            auto_lockdown: raid.auto_lockdown,
+            staff_role: settings.roles.staff.clone(),
+            state: Arc::new(Mutex::new(RaidState::default())),
+        }
+    }
+
+    /// Whether the server is locked down, new members are not given the member role until it is lifted
+    pub async fn is_locked(&self) -> bool {
+        self.state.lock().await.lockdown.is_some()
+    }
+
+    /// Keep track of a new member and alert staff if too many members joined at once
+    /// # Arguments
+    /// * `ctx` - The context of the event
+    /// * `bot` - The bot, used to post the alert
+    /// * `member` - The member that joined
+    /// # Errors
+    /// * If the alert cannot be posted
+    /// * If the server cannot be locked down
+    #[instrument(skip(self, ctx, bot, member))]
+    pub async fn record_join(&self, ctx: &Context, bot: &Bot, member: &Member) -> Result<()> {
+        if !self.enabled {
+            return Ok(());
+        }
+
+        let now = Utc::now();
+        let created_on = Utc
+            .timestamp_opt(member.user.id.created_at().unix_timestamp(), 0)
+            .single()
+            .unwrap_or(now);
+        let (joins, new_accounts) = {
+            let mut state = self.state.lock().await;
+            state.joins.push_back(Join {
+                joined_on: now,
+                new_account: now - created_on < self.new_account_age,
+            });
+            let since = now - self.window;
+            while matches!(state.joins.front(), Some(join) if join.joined_on < since) {
+                state.joins.pop_front();
+            }
+
+            let joins = state.joins.len();
+            let new_accounts = state.joins.iter().filter(|join| join.new_account).count();
+            let raiding = matches!(self.max_joins, Some(max) if joins >= max)
+                || matches!(self.max_new_accounts, Some(max) if new_accounts >= max);
+            // One alert per window is enough while a raid is going on
+            let alerted = matches!(state.last_alert, Some(last_alert) if last_alert >= since);
+            if !raiding || alerted {
+                return Ok(());
+            }
+            state.last_alert = Some(now);
+            (joins, new_accounts)
+        };
+        warn!(
+            "Possible raid: {} joins, {} new accounts",
+            joins, new_accounts
+        );
+
+        let locked = match self.auto_lockdown {
+            true => self.lockdown(ctx, bot).await?,
+            false => false,
+        };
+        let action = match (self.auto_lockdown, locked) {
+            (true, true) => "The server has been locked down, use `/lockdown disable` to lift it",
+            (true, false) => "The server was already locked down",
+            (false, _) => "Use `/lockdown enable` to lock the server down",
+        };
+        let embed = CreateEmbed::default()
+            .title("Possible Raid")
+            .color(Color::RED)
+            .description(action)
+            .field("**Joins**", joins, true)
+            .field("**New Accounts**", new_accounts, true)
+            .field(
+                "**Window**",
+                format!("{}s", self.window.num_seconds()),
+                true,
+            )
+            .clone();
+        let content = format!("<@&{}>", self.staff_role);
+        bot.moderation_manager.alert(ctx, content, embed).await
+    }
+
+    /// Load a lockdown saved before a restart, so new members keep waiting until it is lifted
+    /// # Returns
+    /// Whether the server is locked down
+    /// # Errors
+    /// * If the database is unable to get the lockdown
+    #[instrument(skip(self, bot))]
+    pub async fn load_lockdown(&self, bot: &Bot) -> Result<bool> {
+        let lockdown = bot
+            .firestore_manager
+            .lockdown_get(&self.guild_id.to_string())
+            .await?;
+        let mut state = self.state.lock().await;
+        if state.lockdown.is_none() {
+            state.lockdown = lockdown;
+        }
+        Ok(state.lockdown.is_some())
+    }
+
+    /// Raise the verification level of the server and pause the member role grant
+    /// # Returns
+    /// Whether the server was locked down, `false` if it already was
+    /// # Errors
+    /// * If the verification level cannot be changed
+    #[instrument(skip(self, ctx, bot))]
+    pub async fn lockdown(&self, ctx: &Context, bot: &Bot) -> Result<bool> {
+        let mut state = self.state.lock().await;
+        if state.lockdown.is_some() {
+            return Ok(false);
+        }
+
+        let mut guild_id = self.guild_id;
+        let previous_level = guild_id
+            .to_partial_guild(&ctx.http)
+            .await?
+            .verification_level;
+        // Servers already at a stricter level keep it
+        if previous_level < VerificationLevel::High {
+            guild_id
+                .edit(&ctx.http, |g| g.verification_level(VerificationLevel::High))
+                .await?;
+        }
+        let lockdown = Lockdown {
+            id: guild_id.to_string(),
+            previous_level: previous_level.num() as i64,
+            locked_on: Utc::now(),
+        };
+        if let Err(why) = bot.firestore_manager.lockdown_set(&lockdown).await {
+            warn!("Unable to save the lockdown: {:?}", why);
+        }
+        state.lockdown = Some(lockdown);
+        info!("The server has been locked down");
+        Ok(true)
+    }
+
+    /// Restore the verification level of the server and resume the member role grant,
+    /// a lockdown saved before a restart is lifted too
+    /// # Returns
+    /// Whether the lockdown was lifted, `false` if there was none
+    /// # Errors
+    /// * If the saved lockdown cannot be loaded
+    /// * If the verification level cannot be changed
+    #[instrument(skip(self, ctx, bot))]
+    pub async fn lift_lockdown(&self, ctx: &Context, bot: &Bot) -> Result<bool> {
+        let mut state = self.state.lock().await;
+        if state.lockdown.is_none() {
+            state.lockdown = bot
+                .firestore_manager
+                .lockdown_get(&self.guild_id.to_string())
+                .await?;
+        }
+        let Some(lockdown) = &state.lockdown else {
+            return Ok(false);
+        };
+
+        let mut guild_id = self.guild_id;
+        // The level was only raised if it was below high
+        if let Some(level) = verification_level(lockdown.previous_level)
+            .filter(|level| *level < VerificationLevel::High)
+        {
+            guild_id
+                .edit(&ctx.http, |g| g.verification_level(level))
+                .await?;
+        }
+        if let Err(why) = bot
+            .firestore_manager
+            .lockdown_delete(&guild_id.to_string())
+            .await
+        {
+            warn!("Unable to delete the saved lockdown: {:?}", why);
+        }
+        state.lockdown = None;
+        info!("The server lockdown has been lifted");
+        Ok(true)
+    }
+
+    /// Log who changed the lockdown to the mod channel
+    /// # Errors
+    /// * If the message cannot be posted
+    pub async fn log_lockdown(
+        &self,
+        ctx: &Context,
+        bot: &Bot,
+        moderator: UserId,
+        locked: bool,
+    ) -> Result<()> {
+        let (title, color) = match locked {
+            true => ("Lockdown Enabled", Color::RED),
+            false => ("Lockdown Lifted", Color::DARK_GREEN),
+        };
+        let embed = CreateEmbed::default()
+            .title(title)
+            .color(color)
+            .field("**Moderator**", format!("<@{moderator}>"), true)
+            .clone();
+        bot.moderation_manager.log(ctx, embed).await?;
+        Ok(())
+    }
+}
+
+/// Get the verification level Discord numbers `level`, if it is a known one
+fn verification_level(level: i64) -> Option<VerificationLevel> {
+    match level {
+        0 => Some(VerificationLevel::None),
+        1 => Some(VerificationLevel::Low),
+        2 => Some(VerificationLevel::Medium),
+        3 => Some(VerificationLevel::High),
+        4 => Some(VerificationLevel::Higher),
+        _ => None,
+    }
+}
@@ -1,76 +1,1164 @@
 use std::collections::HashMap;
-use std::fmt::Display;
+use std::str::FromStr;
+use std::sync::Arc;
 
+use chrono::{DateTime, Duration, TimeZone, Utc};
 use color_eyre::Result;
+use hmac::{Hmac, Mac};
+use regex::Regex;
 use serenity::builder::{CreateActionRow, CreateEmbed};
 use serenity::client::Context;
+use serenity::http::HttpError;
 use serenity::model::prelude::application_command::ApplicationCommandInteraction;
-use serenity::model::prelude::component::ButtonStyle;
-use serenity::model::prelude::Message;
-use serenity::prelude::TypeMapKey;
+use serenity::model::prelude::component::{ButtonStyle, InputTextStyle};
+use serenity::model::prelude::interaction::message_component::MessageComponentInteraction;
+use serenity::model::prelude::interaction::modal::ModalSubmitInteraction;
+use serenity::model::prelude::interaction::InteractionResponseType;
+use serenity::model::prelude::{ChannelId, Message, MessageId, UserId};
+use serenity::prelude::{Mutex, TypeMapKey};
+use serenity::utils::Color;
+use sha2::Sha256;
+use tracing::{debug, info, instrument, warn};
 use uuid::Uuid;
 
 use strum::IntoEnumIterator;
-use strum_macros::EnumIter;
+use strum_macros::{AsRefStr, EnumIter, EnumString};
 
-pub struct Report {
+use crate::api::bot::Bot;
+use crate::api::schema::case::{Case, CaseAction};
+use crate::api::schema::report::{
+    AttachmentSnapshot, EmbedSnapshot, Evidence, Report, ReportCategories, ReportStatus,
+};
+use crate::settings::Settings;
+use crate::util::modal::input_value;
+use crate::util::roles::has_role;
+use crate::util::text::truncate;
+
+/// A report that has been started from the context menu but has no category yet
+#[derive(Clone)]
+pub struct PendingReport {
     message: Message,
     original_interaction: ApplicationCommandInteraction,
+    created_on: DateTime<Utc>,
 }
 
-impl TypeMapKey for Report {
-    type Value = HashMap<Uuid, Report>;
+impl TypeMapKey for PendingReport {
+    type Value = HashMap<Uuid, PendingReport>;
 }
 
+/// Who to contact about a report, this is never stored or shown to staff
+pub struct ReporterContact;
+
+impl TypeMapKey for ReporterContact {
+    type Value = HashMap<String, Vec<UserId>>;
+}
+
+/// When someone last sent a report, and who it was about
+#[derive(Clone)]
+pub struct ReporterActivity {
+    target: UserId,
+    reported_on: DateTime<Utc>,
+}
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, EnumIter)]
-enum ReportCategories {
-    Offensive,
-    SpamOrAds,
-    IllegalOrNSFW,
-    Uncomfortable,
-    Other,
+/// Recent reports, keyed by the salted hash of the reporter
+impl TypeMapKey for ReporterActivity {
+    type Value = HashMap<String, Vec<ReporterActivity>>;
 }
 
-impl Display for ReportCategories {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+/// The actions moderators can take from a report card
+#[derive(Debug, Clone, Copy, PartialEq, Eq, EnumIter, EnumString, AsRefStr)]
+enum TriageAction {
+    Acknowledge,
+    Dismiss,
+    DeleteMessage,
+    StrikeAuthor,
+    Escalate,
+}
+
+impl TriageAction {
+    fn label(&self) -> &'static str {
         match self {
-            ReportCategories::Offensive => write!(f, "Offensive"),
-            ReportCategories::SpamOrAds => write!(f, "Spam/Ads"),
-            ReportCategories::IllegalOrNSFW => write!(f, "Illegal or NSFW"),
-            ReportCategories::Uncomfortable => write!(f, "Uncomfortable"),
-            ReportCategories::Other => write!(f, "Other"),
+            Self::Acknowledge => "Acknowledge",
+            Self::Dismiss => "Dismiss",
+            Self::DeleteMessage => "Delete Message",
+            Self::StrikeAuthor => "Strike Author",
+            Self::Escalate => "Escalate",
+        }
+    }
+
+    fn style(&self) -> ButtonStyle {
+        match self {
+            Self::Acknowledge => ButtonStyle::Primary,
+            Self::Dismiss => ButtonStyle::Secondary,
+            Self::DeleteMessage | Self::StrikeAuthor => ButtonStyle::Danger,
+            Self::Escalate => ButtonStyle::Success,
         }
     }
 }
 
+/// Discord's error code for a message that does not exist
+const UNKNOWN_MESSAGE: isize = 10008;
 
-#[derive(Default)]
-pub struct ReportManager;
+/// The longest the pending report lifetime and the target cooldown can be set to
+const MAX_WAIT_SECS: u64 = 60 * 60 * 24 * 7;
 
+// Discord rejects embeds over 6000 characters in total, these leave about 650
+// for the title, footer and the short fields of a report card
+const CARD_CONTENT_LENGTH: usize = 2500;
+const CARD_SNAPSHOT_LENGTH: usize = 700;
+const CARD_DETAILS_LENGTH: usize = 1000;
+const CARD_ONGOING_LENGTH: usize = 200;
+
+#[derive(Clone)]
+pub struct ReportManager {
+    mod_channel: Option<ChannelId>,
+    pending_ttl: Duration,
+    escalation_threshold: usize,
+    /// The secret reporters are hashed with, reports are turned off without one
+    reporter_key: Option<String>,
+    max_per_hour: usize,
+    target_cooldown: Duration,
+    /// Reports are sent one at a time so the limits cannot be raced
+    submit_lock: Arc<Mutex<()>>,
+    /// Reports are triaged one at a time so a report is never acted on twice
+    triage_lock: Arc<Mutex<()>>,
+}
 
 impl ReportManager {
-    pub async fn handle_init_report(&self, message: &Message, ctx: &Context, cmd: &ApplicationCommandInteraction) -> Result<(CreateEmbed, CreateActionRow)> {
-        let report_id = Uuid::new_v4();
-        let mut data = ctx.data.write().await;
-        let reports = data.get_mut::<Report>().ok_or(eyre::eyre!("Unable to get reports"))?;
-        report_id.to_string();
-        let mut action_row = CreateActionRow::default();
-        for category in ReportCategories::iter() {
-            action_row.create_button(|b| {
-                b.label(category)
-                    .style(ButtonStyle::Primary)
-                    .custom_id(format!("report/{}/{}", report_id, category))
-            });
+    #[must_use]
+    pub fn new(settings: &Settings) -> Self {
+        Self {
+            mod_channel: settings
+                .channels
+                .mod_field
+                .parse::<u64>()
+                .ok()
+                .map(ChannelId),
+            pending_ttl: Duration::seconds(
+                settings.reports.pending_ttl_secs.min(MAX_WAIT_SECS) as i64
+            ),
+            escalation_threshold: settings.reports.escalation_threshold,
+            reporter_key: match settings.reports.reporter_key.is_empty() {
+                true => {
+                    warn!("No reporter key is configured, reports are disabled");
+                    None
+                }
+                false => Some(settings.reports.reporter_key.clone()),
+            },
+            max_per_hour: settings.reports.max_per_hour,
+            target_cooldown: Duration::seconds(
+                settings.reports.target_cooldown_secs.min(MAX_WAIT_SECS) as i64,
+            ),
+            submit_lock: Arc::new(Mutex::new(())),
+            triage_lock: Arc::new(Mutex::new(())),
         }
+    }
 
-        let action_row = action_row.clone();
+    pub async fn handle_init_report(
+        &self,
+        message: &Message,
+        ctx: &Context,
+        cmd: &ApplicationCommandInteraction,
+    ) -> Result<(CreateEmbed, CreateActionRow)> {
+        let reporter = self.reporter_hash(cmd.user.id)?;
+        self.check_limits(ctx, &reporter, message.author.id).await?;
+
+        let report_id = Uuid::new_v4();
+        let mut data = ctx.data.write().await;
+        let reports = data
+            .get_mut::<PendingReport>()
+            .ok_or(eyre::eyre!("Unable to get reports"))?;
+        let action_row = category_row(report_id, false);
 
-        let content = format!("[Link to message]({})", message.link());
         let embed = CreateEmbed::default()
             .title(format!("Anonymous report {report_id}"))
-            .description("Please select a category from the buttons below").clone();
-        reports.insert(report_id, Report { message: message.clone(), original_interaction: cmd.clone() });
+            .description("Please select a category from the buttons below")
+            .clone();
+        reports.insert(
+            report_id,
+            PendingReport {
+                message: message.clone(),
+                original_interaction: cmd.clone(),
+                created_on: Utc::now(),
+            },
+        );
         Ok((embed, action_row))
     }
-}
\ No newline at end of file
+
+    /// Handle a category button on a pending report and forward it to the moderators
+    /// # Arguments
+    /// * `ctx` - The context of the interaction
+    /// * `int` - The button interaction, with a custom id of `report/<uuid>/<category>`
+    /// * `bot` - The bot, used for the database
+    /// # Returns
+    /// * `None` if the reporter was asked for details with a modal instead
+    /// # Errors
+    /// * If the custom id is malformed
+    /// * If the report is no longer pending
+    /// * If the report cannot be sent to the mod channel
+    #[instrument(skip(self, ctx, int, bot))]
+    pub async fn handle_button(
+        &self,
+        ctx: &Context,
+        int: &MessageComponentInteraction,
+        bot: &Bot,
+    ) -> Result<Option<String>> {
+        let (report_id, category) = parse_report_id(&int.data.custom_id)?;
+        info!("Report: {} Category: {}", report_id, category);
+
+        if !category.needs_details() {
+            let res = self
+                .submit(ctx, bot, report_id, category, None, None)
+                .await?;
+            return Ok(Some(res));
+        }
+
+        // Make sure the report is still pending before asking for details
+        self.get_pending(ctx, report_id).await?;
+        int.create_interaction_response(&ctx.http, |r| {
+            r.kind(InteractionResponseType::Modal)
+                .interaction_response_data(|d| {
+                    d.custom_id(&int.data.custom_id)
+                        .title(format!("Report: {category}"))
+                        .components(|c| {
+                            c.create_action_row(|row| {
+                                row.create_input_text(|i| {
+                                    i.custom_id("details")
+                                        .label("What happened? (optional)")
+                                        .style(InputTextStyle::Paragraph)
+                                        .max_length(1000)
+                                        .required(false)
+                                })
+                            })
+                            .create_action_row(|row| {
+                                row.create_input_text(|i| {
+                                    i.custom_id("ongoing")
+                                        .label("Is this ongoing? (optional)")
+                                        .placeholder("Yes / No")
+                                        .style(InputTextStyle::Short)
+                                        .max_length(100)
+                                        .required(false)
+                                })
+                            })
+                        })
+                })
+        })
+        .await?;
+
+        Ok(None)
+    }
+
+    /// Handle the details modal of a pending report and forward it to the moderators
+    /// # Arguments
+    /// * `ctx` - The context of the interaction
+    /// * `int` - The modal interaction, with a custom id of `report/<uuid>/<category>`
+    /// * `bot` - The bot, used for the database
+    /// # Errors
+    /// * If the custom id is malformed
+    /// * If the report is no longer pending
+    /// * If the report cannot be sent to the mod channel
+    #[instrument(skip(self, ctx, int, bot))]
+    pub async fn handle_modal(
+        &self,
+        ctx: &Context,
+        int: &ModalSubmitInteraction,
+        bot: &Bot,
+    ) -> Result<String> {
+        let (report_id, category) = parse_report_id(&int.data.custom_id)?;
+        let details = input_value(&int.data.components, "details");
+        let ongoing = input_value(&int.data.components, "ongoing");
+        info!(
+            "Report: {} Category: {} (with details)",
+            report_id, category
+        );
+
+        self.submit(ctx, bot, report_id, category, details, ongoing)
+            .await
+    }
+
+    #[instrument(skip(self, ctx, bot, details, ongoing))]
+    async fn submit(
+        &self,
+        ctx: &Context,
+        bot: &Bot,
+        report_id: Uuid,
+        category: ReportCategories,
+        details: Option<String>,
+        ongoing: Option<String>,
+    ) -> Result<String> {
+        let _guard = self.submit_lock.lock().await;
+        let pending = self.get_pending(ctx, report_id).await?;
+        let mod_channel = self
+            .mod_channel
+            .ok_or(eyre::eyre!("The mod channel is not configured"))?;
+
+        let message = &pending.message;
+        let reporter_id = pending.original_interaction.user.id;
+        let reporter = self.reporter_hash(reporter_id)?;
+        // Several prompts could have been opened before any of them was sent
+        self.check_limits(ctx, &reporter, message.author.id).await?;
+        let activity = ReporterActivity {
+            target: message.author.id,
+            reported_on: Utc::now(),
+        };
+        let existing = self.get_open_for_message(ctx, message.id).await?;
+
+        let stored_id = match existing {
+            Some(mut report) => {
+                info!("Adding report to existing report {}", report.id);
+                // A retry after a failed card should not count the same report twice
+                let new_reporter = !report.reporters.contains(&reporter);
+                if new_reporter || !report.categories.contains(&category) {
+                    report.categories.push(category);
+                }
+                if new_reporter {
+                    report.reporters.push(reporter.clone());
+                }
+                if let Some(details) = details {
+                    report.details = Some(match report.details {
+                        Some(previous) => format!("{previous}\n---\n{details}"),
+                        None => details,
+                    });
+                }
+                if ongoing.is_some() {
+                    report.ongoing = ongoing;
+                }
+
+                if !report.escalated && report.reporters.len() >= self.escalation_threshold {
+                    report.escalated = true;
+                    let content = format!(
+                        "<@&{}> {} people have independently reported this message: {}",
+                        bot.settings.roles.staff,
+                        report.reporters.len(),
+                        report.message_link
+                    );
+                    mod_channel
+                        .send_message(&ctx.http, |m| m.content(content))
+                        .await?;
+                }
+
+                self.post_card(ctx, mod_channel, &mut report).await?;
+                bot.firestore_manager.report_update(ctx, &report).await?;
+                report.id
+            }
+            None => {
+                let mut report = Report {
+                    id: report_id.to_string(),
+                    category,
+                    categories: vec![category],
+                    reporters: vec![reporter.clone()],
+                    message_link: message.link(),
+                    message_id: message.id.to_string(),
+                    channel_id: message.channel_id.to_string(),
+                    reported_user: message.author.id.to_string(),
+                    content: message.content.clone(),
+                    evidence: Some(snapshot(message)),
+                    details,
+                    ongoing,
+                    created_on: Utc::now(),
+                    status: ReportStatus::Open,
+                    handled_by: None,
+                    handled_on: None,
+                    card_message: None,
+                    escalated: false,
+                    thread: None,
+                };
+
+                // Saved before the card is posted so a failed save never leaves a card behind,
+                // if posting fails the retry finds the report and posts its card
+                bot.firestore_manager.report_add(ctx, &report).await?;
+                self.post_card(ctx, mod_channel, &mut report).await?;
+                bot.firestore_manager.report_update(ctx, &report).await?;
+                report.id
+            }
+        };
+
+        let mut data = ctx.data.write().await;
+        let pending_reports = data
+            .get_mut::<PendingReport>()
+            .ok_or(eyre::eyre!("Unable to get reports"))?;
+        pending_reports.remove(&report_id);
+
+        let cutoff = Utc::now() - self.activity_window();
+        let activities = data
+            .get_mut::<ReporterActivity>()
+            .ok_or(eyre::eyre!("Unable to get reporter activity"))?;
+        let reporter_activity = activities.entry(reporter).or_default();
+        reporter_activity.retain(|a| a.reported_on > cutoff);
+        reporter_activity.push(activity);
+
+        let contacts = data
+            .get_mut::<ReporterContact>()
+            .ok_or(eyre::eyre!("Unable to get reporter contacts"))?;
+        let contacts = contacts.entry(stored_id).or_default();
+        if !contacts.contains(&reporter_id) {
+            contacts.push(reporter_id);
+        }
+
+        Ok("Thank you, your report has been sent to the moderators. Your identity has not been shared with them.".to_string())
+    }
+
+    /// Post the card of a report to the mod channel, or update it if it was already posted
+    async fn post_card(
+        &self,
+        ctx: &Context,
+        mod_channel: ChannelId,
+        report: &mut Report,
+    ) -> Result<()> {
+        let embed = self.mod_card(report);
+        // The reporter has just been remembered as a contact
+        let rows = self.card_rows(report, true);
+        match &report.card_message {
+            Some(card) => {
+                mod_channel
+                    .edit_message(&ctx.http, MessageId(card.parse::<u64>()?), |m| {
+                        m.set_embed(embed).components(|c| c.set_action_rows(rows))
+                    })
+                    .await?;
+            }
+            None => {
+                let card = mod_channel
+                    .send_message(&ctx.http, |m| {
+                        m.set_embed(embed).components(|c| c.set_action_rows(rows))
+                    })
+                    .await?;
+                report.card_message = Some(card.id.to_string());
+            }
+        }
+        Ok(())
+    }
+
+    /// Get the open report of a message, if it has already been reported
+    #[instrument(skip(self, ctx))]
+    async fn get_open_for_message(
+        &self,
+        ctx: &Context,
+        message_id: MessageId,
+    ) -> Result<Option<Report>> {
+        let data = ctx.data.read().await;
+        let reports = data
+            .get::<Report>()
+            .ok_or(eyre::eyre!("Unable to get reports"))?;
+
+        let message_id = message_id.to_string();
+        let report = reports
+            .values()
+            .find(|r| r.message_id == message_id && r.status.is_open());
+        Ok(report.cloned())
+    }
+
+    /// Make sure a reporter has not hit the hourly limit or reported the same person too recently
+    /// # Arguments
+    /// * `ctx` - The context of the interaction
+    /// * `reporter` - The salted hash of the reporter
+    /// * `target` - The author of the reported message
+    /// # Errors
+    /// * If a limit has been hit, with a message for the reporter
+    #[instrument(skip(self, ctx, reporter))]
+    async fn check_limits(&self, ctx: &Context, reporter: &str, target: UserId) -> Result<()> {
+        let data = ctx.data.read().await;
+        let activities = data
+            .get::<ReporterActivity>()
+            .ok_or(eyre::eyre!("Unable to get reporter activity"))?;
+        let Some(activity) = activities.get(reporter) else {
+            return Ok(());
+        };
+
+        let now = Utc::now();
+        let hour_ago = now - Duration::hours(1);
+        let recent: Vec<&ReporterActivity> = activity
+            .iter()
+            .filter(|a| a.reported_on > hour_ago)
+            .collect();
+        if recent.len() >= self.max_per_hour {
+            let retry =
+                recent.iter().map(|a| a.reported_on).min().unwrap_or(now) + Duration::hours(1);
+            info!("Reporter hit the hourly report limit");
+            return Err(eyre::eyre!(
+                "You can only send {} reports per hour, please try again <t:{}:R>",
+                self.max_per_hour,
+                retry.timestamp()
+            ));
+        }
+
+        let last_for_target = activity
+            .iter()
+            .filter(|a| a.target == target)
+            .map(|a| a.reported_on)
+            .max();
+        if let Some(last) = last_for_target {
+            if now - last < self.target_cooldown {
+                info!("Reporter hit the report cooldown for {}", target);
+                return Err(eyre::eyre!(
+                    "You have already reported this person recently, please try again <t:{}:R>",
+                    (last + self.target_cooldown).timestamp()
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// How long report activity needs to be remembered for the limits
+    fn activity_window(&self) -> Duration {
+        std::cmp::max(Duration::hours(1), self.target_cooldown)
+    }
+
+    /// Hash a reporter so repeated reports can be recognised without storing who made them,
+    /// the key never leaves the settings so the hashes cannot be reversed from the database
+    fn reporter_hash(&self, user: UserId) -> Result<String> {
+        let key = self.reporter_key.as_ref().ok_or(eyre::eyre!(
+            "Reports are not available right now, please contact staff"
+        ))?;
+        let mut mac = Hmac::<Sha256>::new_from_slice(key.as_bytes())
+            .map_err(|_| eyre::eyre!("Invalid reporter key"))?;
+        mac.update(user.to_string().as_bytes());
+        Ok(format!("{:x}", mac.finalize().into_bytes()))
+    }
+
+    /// Drop pending reports that never had a category picked
+    /// and disable the buttons on their prompts
+    /// # Arguments
+    /// * `ctx` - The context of the bot
+    /// # Errors
+    /// * If the cache is unable to be accessed
+    #[instrument(skip(self, ctx))]
+    pub async fn sweep_pending(&self, ctx: &Context) -> Result<()> {
+        let now = Utc::now();
+        let stale: Vec<(Uuid, PendingReport)> = {
+            let mut data = ctx.data.write().await;
+            let reports = data
+                .get_mut::<PendingReport>()
+                .ok_or(eyre::eyre!("Unable to get reports"))?;
+            let stale_ids: Vec<Uuid> = reports
+                .iter()
+                .filter(|(_, r)| now - r.created_on > self.pending_ttl)
+                .map(|(id, _)| *id)
+                .collect();
+            stale_ids
+                .into_iter()
+                .filter_map(|id| reports.remove(&id).map(|r| (id, r)))
+                .collect()
+        };
+        if stale.is_empty() {
+            return Ok(());
+        }
+
+        let mut updated = 0;
+        for (report_id, report) in &stale {
+            let action_row = category_row(*report_id, true);
+            let res = report
+                .original_interaction
+                .edit_original_interaction_response(&ctx.http, |r| {
+                    r.content("This report has expired, please report the message again")
+                        .components(|c| c.add_action_row(action_row))
+                })
+                .await;
+            match res {
+                Ok(_) => updated += 1,
+                Err(why) => debug!("Unable to expire report {}: {:?}", report_id, why),
+            }
+        }
+        info!(
+            "Swept {} abandoned reports, disabled {} prompts",
+            stale.len(),
+            updated
+        );
+        Ok(())
+    }
+
+    #[instrument(skip(self, ctx))]
+    async fn get_pending(&self, ctx: &Context, report_id: Uuid) -> Result<PendingReport> {
+        let data = ctx.data.read().await;
+        let reports = data
+            .get::<PendingReport>()
+            .ok_or(eyre::eyre!("Unable to get reports"))?;
+
+        let report = reports.get(&report_id).ok_or(eyre::eyre!(
+            "This report is no longer pending, please report the message again"
+        ))?;
+        Ok(report.to_owned())
+    }
+
+    /// Handle a triage button on a report card in the mod channel
+    /// # Arguments
+    /// * `ctx` - The context of the interaction
+    /// * `int` - The button interaction, with a custom id of `triage/<report id>/<action>`
+    /// * `bot` - The bot, used for settings and the database
+    /// # Errors
+    /// * If the member pressing the button is not staff
+    /// * If the custom id is malformed or the report cannot be found
+    /// * If the report is already closed
+    /// * If the action or the report update fails
+    #[instrument(skip(self, ctx, int, bot))]
+    pub async fn handle_triage(
+        &self,
+        ctx: &Context,
+        int: &MessageComponentInteraction,
+        bot: &Bot,
+    ) -> Result<(CreateEmbed, Vec<CreateActionRow>)> {
+        if !has_role(int.member.as_ref(), &bot.settings.roles.staff) {
+            return Err(eyre::eyre!("Only staff can triage reports"));
+        }
+
+        let data = int.data.custom_id.clone();
+        let reg = Regex::new(r"triage/([^/]+)/([^/]+)")?;
+        let matches = reg
+            .captures(&data)
+            .ok_or(eyre::eyre!("Unable to get matches"))?;
+        let report_id = matches
+            .get(1)
+            .ok_or(eyre::eyre!("Unable to get report id"))?
+            .as_str();
+        let action = matches
+            .get(2)
+            .ok_or(eyre::eyre!("Unable to get action"))?
+            .as_str();
+        let action = TriageAction::from_str(action)?;
+        info!("Report: {} Action: {:?}", report_id, action);
+
+        let _guard = self.triage_lock.lock().await;
+        let mut report = self.get_report(ctx, bot, report_id).await?;
+        if !report.status.is_open() {
+            return Err(eyre::eyre!(
+                "Report {} is already closed ({})",
+                report.id,
+                report.status
+            ));
+        }
+        report.status = match action {
+            TriageAction::Acknowledge => ReportStatus::Acknowledged,
+            TriageAction::Dismiss => ReportStatus::Dismissed,
+            TriageAction::DeleteMessage => {
+                let channel = ChannelId(report.channel_id.parse::<u64>()?);
+                let message = MessageId(report.message_id.parse::<u64>()?);
+                match channel.delete_message(&ctx.http, message).await {
+                    Ok(()) => {}
+                    // The author or another moderator deleted it first
+                    Err(why) if is_unknown_message(&why) => {
+                        info!("Reported message {} was already deleted", message);
+                    }
+                    Err(why) => return Err(why.into()),
+                }
+                ReportStatus::Resolved
+            }
+            TriageAction::StrikeAuthor => {
+                let author = UserId(report.reported_user.parse::<u64>()?);
+                let reason = format!("Reported message: {}", report.message_link);
+                bot.moderation_manager
+                    .check_hierarchy(ctx, author, int.user.id)
+                    .await?;
+                bot.moderation_manager
+                    .strike(ctx, bot, author, int.user.id, &reason, Some(&report.id))
+                    .await?;
+                ReportStatus::Resolved
+            }
+            TriageAction::Escalate => {
+                let content = format!(
+                    "<@&{}> report {} has been escalated by <@{}>: {}",
+                    bot.settings.roles.director,
+                    report.id,
+                    int.user.id,
+                    int.message.link()
+                );
+                int.channel_id
+                    .send_message(&ctx.http, |m| m.content(content))
+                    .await?;
+                ReportStatus::Acknowledged
+            }
+        };
+        report.handled_by = Some(int.user.id.to_string());
+        report.handled_on = Some(Utc::now());
+        bot.firestore_manager.report_update(ctx, &report).await?;
+
+        let resolution = match report.status {
+            ReportStatus::Resolved => Some(CaseAction::ReportResolved),
+            ReportStatus::Dismissed => Some(CaseAction::ReportDismissed),
+            ReportStatus::Open | ReportStatus::Acknowledged => None,
+        };
+        if let Some(resolution) = resolution {
+            let mut case = Case::new(
+                resolution,
+                &report.reported_user,
+                &int.user.id.to_string(),
+                &format!("{} report: {}", report.category, action.label()),
+            );
+            case.report = Some(report.id.clone());
+            bot.moderation_manager.open_case(ctx, bot, case).await?;
+        }
+
+        let can_contact = self.get_contacts(ctx, &report.id).await.is_ok();
+        Ok((self.mod_card(&report), self.card_rows(&report, can_contact)))
+    }
+
+    /// Handle the follow up buttons, either a moderator asking the reporters a question
+    /// or a reporter replying from their DMs, by opening a modal for the message
+    /// # Arguments
+    /// * `ctx` - The context of the interaction
+    /// * `int` - The button interaction, with a custom id of `followup/<ask|reply>/<report id>`
+    /// * `bot` - The bot, used for settings
+    /// # Errors
+    /// * If the member is not allowed to use the button
+    /// * If the modal cannot be opened
+    #[instrument(skip(self, ctx, int, bot))]
+    pub async fn handle_followup_button(
+        &self,
+        ctx: &Context,
+        int: &MessageComponentInteraction,
+        bot: &Bot,
+    ) -> Result<()> {
+        let (action, report_id) = parse_followup_id(&int.data.custom_id)?;
+        let (title, label) = match action.as_str() {
+            "ask" => {
+                if !has_role(int.member.as_ref(), &bot.settings.roles.staff) {
+                    return Err(eyre::eyre!("Only staff can contact reporters"));
+                }
+                if let Err(why) = self.get_contacts(ctx, &report_id).await {
+                    // Reporters are not stored, so the button will not work again
+                    let report = self.get_report(ctx, bot, &report_id).await?;
+                    let rows = self.card_rows(&report, false);
+                    int.channel_id
+                        .edit_message(&ctx.http, int.message.id, |m| {
+                            m.components(|c| c.set_action_rows(rows))
+                        })
+                        .await?;
+                    return Err(why);
+                }
+                ("Ask the reporter", "Question")
+            }
+            "reply" => {
+                self.check_contact(ctx, &report_id, int.user.id).await?;
+                ("Reply to the moderators", "Reply")
+            }
+            _ => return Err(eyre::eyre!("Unable to get action")),
+        };
+
+        int.create_interaction_response(&ctx.http, |r| {
+            r.kind(InteractionResponseType::Modal)
+                .interaction_response_data(|d| {
+                    d.custom_id(&int.data.custom_id)
+                        .title(title)
+                        .components(|c| {
+                            c.create_action_row(|row| {
+                                row.create_input_text(|i| {
+                                    i.custom_id("message")
+                                        .label(label)
+                                        .style(InputTextStyle::Paragraph)
+                                        .max_length(1000)
+                                        .required(true)
+                                })
+                            })
+                        })
+                })
+        })
+        .await?;
+        Ok(())
+    }
+
+    /// Relay a follow up message between the moderators and the reporters,
+    /// questions are sent to the reporters' DMs and replies to the thread of the report card
+    /// # Arguments
+    /// * `ctx` - The context of the interaction
+    /// * `int` - The modal interaction, with a custom id of `followup/<ask|reply>/<report id>`
+    /// * `bot` - The bot, used for settings and the database
+    /// # Errors
+    /// * If the member is not allowed to send the message
+    /// * If the reporters can no longer be contacted
+    /// * If the message cannot be relayed
+    #[instrument(skip(self, ctx, int, bot))]
+    pub async fn handle_followup_modal(
+        &self,
+        ctx: &Context,
+        int: &ModalSubmitInteraction,
+        bot: &Bot,
+    ) -> Result<String> {
+        let (action, report_id) = parse_followup_id(&int.data.custom_id)?;
+        let text = input_value(&int.data.components, "message")
+            .ok_or(eyre::eyre!("No message provided"))?;
+        let mut report = self.get_report(ctx, bot, &report_id).await?;
+
+        match action.as_str() {
+            "ask" => {
+                if !has_role(int.member.as_ref(), &bot.settings.roles.staff) {
+                    return Err(eyre::eyre!("Only staff can contact reporters"));
+                }
+                let contacts = self.get_contacts(ctx, &report_id).await?;
+                // One reporter with closed DMs should not stop the others from being asked
+                let mut failed = 0;
+                for contact in &contacts {
+                    if let Err(why) = self.send_question(ctx, *contact, &text, &report).await {
+                        warn!("Unable to send a follow up question: {:?}", why);
+                        failed += 1;
+                    }
+                }
+                if failed == contacts.len() {
+                    return Err(eyre::eyre!(
+                        "The question could not be delivered, the reporter may have closed their DMs"
+                    ));
+                }
+
+                let delivered = match failed {
+                    0 => String::new(),
+                    _ => format!(
+                        " ({} of {} reporters could not be reached)",
+                        failed,
+                        contacts.len()
+                    ),
+                };
+                let thread = self.get_thread(ctx, bot, &mut report).await?;
+                thread
+                    .send_message(&ctx.http, |m| {
+                        m.content(format!("<@{}> asked the reporter{delivered}:", int.user.id))
+                            .embed(|e| e.description(&text).color(Color::BLUE))
+                    })
+                    .await?;
+                info!("Sent a follow up question for report {}", report.id);
+                Ok(format!(
+                    "Your question has been sent to the reporter{delivered}"
+                ))
+            }
+            "reply" => {
+                self.check_contact(ctx, &report_id, int.user.id).await?;
+                let thread = self.get_thread(ctx, bot, &mut report).await?;
+                thread
+                    .send_message(&ctx.http, |m| {
+                        m.content("The reporter replied:")
+                            .embed(|e| e.description(&text).color(Color::DARK_GREEN))
+                    })
+                    .await?;
+                info!("Relayed a follow up reply for report {}", report.id);
+                Ok("Your reply has been sent to the moderators, your identity has not been shared with them".to_string())
+            }
+            _ => Err(eyre::eyre!("Unable to get action")),
+        }
+    }
+
+    /// Send a moderator's question to a reporter's DMs
+    async fn send_question(
+        &self,
+        ctx: &Context,
+        contact: UserId,
+        text: &str,
+        report: &Report,
+    ) -> Result<()> {
+        let dm = contact.create_dm_channel(&ctx.http).await?;
+        dm.send_message(&ctx.http, |m| {
+            m.embed(|e| {
+                e.title("A moderator has a question about your report")
+                    .description(text)
+                    .field(
+                        "**Reported message**",
+                        format!("[Jump to message]({})", report.message_link),
+                        false,
+                    )
+                    .footer(|f| f.text("Your identity will not be shared with the moderators"))
+                    .color(Color::BLUE)
+            })
+            .components(|c| {
+                c.create_action_row(|row| {
+                    row.create_button(|b| {
+                        b.label("Reply")
+                            .style(ButtonStyle::Primary)
+                            .custom_id(format!("followup/reply/{}", report.id))
+                    })
+                })
+            })
+        })
+        .await?;
+        Ok(())
+    }
+
+    /// Get the people who sent a report
+    #[instrument(skip(self, ctx))]
+    async fn get_contacts(&self, ctx: &Context, report_id: &str) -> Result<Vec<UserId>> {
+        let data = ctx.data.read().await;
+        let contacts = data
+            .get::<ReporterContact>()
+            .ok_or(eyre::eyre!("Unable to get reporter contacts"))?;
+
+        match contacts.get(report_id) {
+            Some(contacts) if !contacts.is_empty() => Ok(contacts.clone()),
+            _ => Err(eyre::eyre!(
+                "The reporter can no longer be contacted, reporters are only remembered until the bot restarts"
+            )),
+        }
+    }
+
+    /// Make sure a user is one of the people who sent a report
+    #[instrument(skip(self, ctx, user))]
+    async fn check_contact(&self, ctx: &Context, report_id: &str, user: UserId) -> Result<()> {
+        let contacts = self.get_contacts(ctx, report_id).await?;
+        match contacts.contains(&user) {
+            true => Ok(()),
+            false => Err(eyre::eyre!("You did not send this report")),
+        }
+    }
+
+    /// Get the thread of a report card, starting one if the card has none yet
+    #[instrument(skip(self, ctx, bot, report))]
+    async fn get_thread(&self, ctx: &Context, bot: &Bot, report: &mut Report) -> Result<ChannelId> {
+        if let Some(thread) = &report.thread {
+            return Ok(ChannelId(thread.parse::<u64>()?));
+        }
+
+        let mod_channel = self
+            .mod_channel
+            .ok_or(eyre::eyre!("The mod channel is not configured"))?;
+        let card = report
+            .card_message
+            .as_ref()
+            .ok_or(eyre::eyre!("This report has no card"))?
+            .parse::<u64>()?;
+        let thread = mod_channel
+            .create_public_thread(&ctx.http, card, |t| {
+                t.name(format!("Report {} follow up", report.id))
+            })
+            .await?;
+
+        report.thread = Some(thread.id.to_string());
+        bot.firestore_manager.report_update(ctx, report).await?;
+        Ok(thread.id)
+    }
+
+    /// Get a report from the cache, falling back to the database for closed reports
+    #[instrument(skip(self, ctx, bot))]
+    async fn get_report(&self, ctx: &Context, bot: &Bot, report_id: &str) -> Result<Report> {
+        {
+            let data = ctx.data.read().await;
+            let reports = data
+                .get::<Report>()
+                .ok_or(eyre::eyre!("Unable to get reports"))?;
+            if let Some(report) = reports.get(report_id) {
+                return Ok(report.to_owned());
+            }
+        }
+
+        let report: Option<Report> = bot
+            .firestore_manager
+            .client
+            .fluent()
+            .select()
+            .by_id_in("report")
+            .obj()
+            .one(report_id)
+            .await?;
+        report.ok_or(eyre::eyre!("Unable to find report {}", report_id))
+    }
+
+    /// Build the triage and follow up buttons of a report card,
+    /// reporters can only be contacted until the bot restarts
+    fn card_rows(&self, report: &Report, can_contact: bool) -> Vec<CreateActionRow> {
+        let mut triage_row = CreateActionRow::default();
+        for action in TriageAction::iter() {
+            triage_row.create_button(|b| {
+                b.label(action.label())
+                    .style(action.style())
+                    .custom_id(format!("triage/{}/{}", report.id, action.as_ref()))
+                    .disabled(!report.status.is_open())
+            });
+        }
+
+        let mut followup_row = CreateActionRow::default();
+        followup_row.create_button(|b| {
+            b.label("Ask reporter")
+                .style(ButtonStyle::Secondary)
+                .custom_id(format!("followup/ask/{}", report.id))
+                .disabled(!report.status.is_open() || !can_contact)
+        });
+
+        vec![triage_row, followup_row]
+    }
+
+    fn mod_card(&self, report: &Report) -> CreateEmbed {
+        let content = match report.content.is_empty() {
+            true => "*No text content*".to_owned(),
+            false => truncate(&report.content, CARD_CONTENT_LENGTH),
+        };
+
+        let author = match &report.evidence {
+            Some(evidence) => format!("<@{}> ({})", report.reported_user, evidence.author_tag),
+            None => format!("<@{}>", report.reported_user),
+        };
+        let message = match &report.evidence {
+            Some(evidence) => format!(
+                "[Jump to message]({}), sent <t:{}:f>\nThe message may have been deleted since, this card shows a snapshot",
+                report.message_link,
+                evidence.sent_on.timestamp()
+            ),
+            None => format!("[Jump to message]({})", report.message_link),
+        };
+
+        let mut embed = CreateEmbed::default()
+            .title(format!("Anonymous report: {}", report.category))
+            .color(Color::RED)
+            .description(content)
+            .field("**Category**", report.category, true)
+            .field("**Author**", author, true)
+            .field("**Channel**", format!("<#{}>", report.channel_id), true)
+            .field("**Message**", message, false)
+            .footer(|f| f.text(format!("Report {}", report.id)))
+            .timestamp(report.created_on.to_rfc3339())
+            .clone();
+
+        if let Some(evidence) = &report.evidence {
+            if !evidence.attachments.is_empty() {
+                let attachments = evidence
+                    .attachments
+                    .iter()
+                    .map(|a| format!("[{}]({})", a.filename, a.url))
+                    .collect::<Vec<_>>()
+                    .join("\n");
+                embed.field(
+                    "**Attachments**",
+                    truncate(&attachments, CARD_SNAPSHOT_LENGTH),
+                    false,
+                );
+            }
+            if !evidence.embeds.is_empty() {
+                let embeds = evidence
+                    .embeds
+                    .iter()
+                    .map(|e| {
+                        let title = e.title.as_deref().unwrap_or("Untitled embed");
+                        match &e.description {
+                            Some(description) => format!("**{title}**: {description}"),
+                            None => format!("**{title}**"),
+                        }
+                    })
+                    .collect::<Vec<_>>()
+                    .join("\n");
+                embed.field("**Embeds**", truncate(&embeds, CARD_SNAPSHOT_LENGTH), false);
+            }
+        }
+
+        if report.categories.len() > 1 {
+            let mut breakdown: Vec<(ReportCategories, usize)> = ReportCategories::iter()
+                .map(|c| (c, report.categories.iter().filter(|r| **r == c).count()))
+                .filter(|(_, count)| *count > 0)
+                .collect();
+            breakdown.sort_by_key(|(_, count)| std::cmp::Reverse(*count));
+            let breakdown = breakdown
+                .iter()
+                .map(|(c, count)| format!("{c}: {count}"))
+                .collect::<Vec<_>>()
+                .join("\n");
+            embed
+                .field(
+                    "**Reports**",
+                    format!(
+                        "{} from {} reporters",
+                        report.categories.len(),
+                        report.reporters.len()
+                    ),
+                    true,
+                )
+                .field("**Categories**", breakdown, true);
+        }
+        if let Some(details) = &report.details {
+            embed.field("**Details**", truncate(details, CARD_DETAILS_LENGTH), false);
+        }
+        if let Some(ongoing) = &report.ongoing {
+            embed.field("**Ongoing**", truncate(ongoing, CARD_ONGOING_LENGTH), true);
+        }
+        embed.field("**Status**", report.status, true);
+        if let (Some(moderator), Some(handled_on)) = (&report.handled_by, report.handled_on) {
+            embed.field(
+                "**Handled by**",
+                format!("<@{}> <t:{}:f>", moderator, handled_on.timestamp()),
+                true,
+            );
+        }
+        embed
+    }
+}
+
+/// Capture what a message looked like, so it can still be reviewed after it is deleted
+fn snapshot(message: &Message) -> Evidence {
+    Evidence {
+        author_tag: message.author.tag(),
+        sent_on: Utc
+            .timestamp_opt(message.timestamp.unix_timestamp(), 0)
+            .single()
+            .unwrap_or_else(Utc::now),
+        embeds: message
+            .embeds
+            .iter()
+            .map(|e| EmbedSnapshot {
+                title: e.title.clone(),
+                description: e.description.clone(),
+                url: e.url.clone(),
+            })
+            .collect(),
+        attachments: message
+            .attachments
+            .iter()
+            .map(|a| AttachmentSnapshot {
+                filename: a.filename.clone(),
+                url: a.url.clone(),
+            })
+            .collect(),
+    }
+}
+
+/// Build the category buttons of a pending report
+fn category_row(report_id: Uuid, disabled: bool) -> CreateActionRow {
+    let mut action_row = CreateActionRow::default();
+    for category in ReportCategories::iter() {
+        action_row.create_button(|b| {
+            b.label(category)
+                .style(ButtonStyle::Primary)
+                .custom_id(format!("report/{}/{}", report_id, category.as_ref()))
+                .disabled(disabled)
+        });
+    }
+    action_row
+}
+
+/// Parse a `followup/<action>/<report id>` custom id
+fn parse_followup_id(custom_id: &str) -> Result<(String, String)> {
+    let reg = Regex::new(r"followup/([^/]+)/([^/]+)")?;
+    let matches = reg
+        .captures(custom_id)
+        .ok_or(eyre::eyre!("Unable to get matches"))?;
+    let action = matches
+        .get(1)
+        .ok_or(eyre::eyre!("Unable to get action"))?
+        .as_str();
+    let report_id = matches
+        .get(2)
+        .ok_or(eyre::eyre!("Unable to get report id"))?
+        .as_str();
+
+    Ok((action.to_string(), report_id.to_string()))
+}
+
+/// Parse a `report/<uuid>/<category>` custom id
+fn parse_report_id(custom_id: &str) -> Result<(Uuid, ReportCategories)> {
+    let reg = Regex::new(r"report/([^/]+)/([^/]+)")?;
+    let matches = reg
+        .captures(custom_id)
+        .ok_or(eyre::eyre!("Unable to get matches"))?;
+    let report_id = matches
+        .get(1)
+        .ok_or(eyre::eyre!("Unable to get report id"))?
+        .as_str();
+    let category = matches
+        .get(2)
+        .ok_or(eyre::eyre!("Unable to get category"))?
+        .as_str();
+
+    Ok((
+        Uuid::parse_str(report_id)?,
+        ReportCategories::from_str(category)?,
+    ))
+}
+
+/// Whether Discord refused a request because the message no longer exists
+fn is_unknown_message(why: &serenity::Error) -> bool {
+    match why {
+        serenity::Error::Http(http) => matches!(
+            http.as_ref(),
+            HttpError::UnsuccessfulRequest(res) if res.error.code == UNKNOWN_MESSAGE
+        ),
+        _ => false,
+    }
+}
@@ -0,0 +1,452 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use chrono::{DateTime, Duration, Utc};
+use color_eyre::Result;
+use regex::Regex;
+use serenity::{
+    builder::CreateActionRow,
+    model::prelude::{
+        component::{ActionRowComponent, ButtonStyle, InputTextStyle},
+        interaction::{
+            message_component::MessageComponentInteraction, modal::ModalSubmitInteraction,
+            InteractionResponseType,
+        },
+        ChannelId, GuildId, Message, RoleId, UserId,
+    },
+    prelude::{Context, Mutex},
+};
+use tracing::{error, info, instrument, warn};
+use uuid::Uuid;
+
+use crate::{
+    api::{bot::Bot, schema::discord::Discord},
+    settings::Settings,
+    util::{
+        mail::{transport, MailTransport},
+        modal::input_value,
+        roles::has_role,
+    },
+};
+
+/// The custom id of the button on the verification panel
+const VERIFY_BUTTON: &str = "verify/open";
+
+/// How long a member has to wait before asking for another code
+const RESEND_COOLDOWN_SECS: i64 = 60;
+
+/// The longest a code can be valid for
+const MAX_CODE_MINUTES: i64 = 60 * 24;
+
+/// A code that was emailed to a member and not entered yet
+struct PendingCode {
+    name: String,
+    net_id: String,
+    code: String,
+    sent_on: DateTime<Utc>,
+    expires_on: DateTime<Utc>,
+    attempts: u32,
+}
+
+#[derive(Clone)]
+pub struct VerificationManager {
+    guild_id: GuildId,
+    channel: Option<ChannelId>,
+    member_role: Option<RoleId>,
+    /// A net-id is three letters followed by six digits, e.g. abc123456
+    net_id: Regex,
+    email_domain: String,
+    /// `None` when no transport is configured, codes are not sent then
+    mailer: Option<Arc<dyn MailTransport>>,
+    code_ttl: Duration,
+    max_attempts: u32,
+    codes: Arc<Mutex<HashMap<UserId, PendingCode>>>,
+}
+
+impl VerificationManager {
+    #[must_use]
+    pub fn new(settings: &Settings) -> Self {
+        let verification = &settings.verification;
+        let mailer = match &verification.mail {
+            Some(mail) => match transport(mail) {
+                Ok(mailer) => Some(mailer),
+                Err(why) => {
+                    error!("Invalid mail settings, verification is disabled: {:?}", why);
+                    None
+                }
+            },
+            None => {
+                warn!("No mail transport is configured, verification is disabled");
+                None
+            }
+        };
+        Self {
+            guild_id: GuildId(settings.guild),
+            channel: settings
+                .channels
+                .verification
+                .parse::<u64>()
+                .ok()
+                .map(ChannelId),
+            member_role: settings.roles.member.parse::<u64>().ok().map(RoleId),
+            net_id: Regex::new(r"^[a-z]{3}\d{6}$").expect("The net-id pattern is valid"),
+            email_domain: verification.email_domain.clone(),
+            mailer,
+            code_ttl: Duration::minutes(verification.code_minutes.clamp(1, MAX_CODE_MINUTES)),
+            max_attempts: verification.max_attempts,
+            codes: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    /// Post the verification panel, unless the channel already has one,
+    /// this is only done once on startup rather than on every reconnect
+    /// # Errors
+    /// * If the verification channel is not configured
+    /// * If the channel cannot be read or posted to
+    #[instrument(skip(self, ctx, bot))]
+    pub async fn post_panel(&self, ctx: &Context, bot: &Bot) -> Result<()> {
+        let channel = self
+            .channel
+            .ok_or(eyre::eyre!("The verification channel is not configured"))?;
+        let bot_id = bot.user_id()?;
+        let messages = channel.messages(&ctx.http, |m| m.limit(50)).await?;
+        if messages
+            .iter()
+            .any(|msg| msg.author.id == bot_id && is_panel(msg))
+        {
+            return Ok(());
+        }
+
+        channel
+            .send_message(&ctx.http, |m| {
+                m.content(
+                    "> :wave: Welcome! Verify with your name and net-id to get access to the rest of the server.\n> :e_mail: We will email a code to your university address to confirm it is you.",
+                )
+                .components(|c| {
+                    c.create_action_row(|row| {
+                        row.create_button(|b| {
+                            b.custom_id(VERIFY_BUTTON)
+                                .label("Verify")
+                                .style(ButtonStyle::Success)
+                        })
+                    })
+                })
+            })
+            .await?;
+        info!("Posted the verification panel");
+        Ok(())
+    }
+
+    /// Open the verification form, or the form for the emailed code
+    /// # Arguments
+    /// * `ctx` - The context of the interaction
+    /// * `int` - The button interaction, with a custom id of `verify/open` or `verify/code`
+    /// * `bot` - The bot, used for the settings
+    /// # Errors
+    /// * If the member is already verified
+    /// * If the member has no code waiting to be entered
+    /// * If the modal cannot be sent
+    #[instrument(skip(self, ctx, int, bot))]
+    pub async fn handle_button(
+        &self,
+        ctx: &Context,
+        int: &MessageComponentInteraction,
+        bot: &Bot,
+    ) -> Result<()> {
+        if has_role(int.member.as_ref(), &bot.settings.roles.member) {
+            return Err(eyre::eyre!("You are already verified"));
+        }
+
+        match int.data.custom_id.as_str() {
+            VERIFY_BUTTON => {
+                int.create_interaction_response(&ctx.http, |r| {
+                    r.kind(InteractionResponseType::Modal)
+                        .interaction_response_data(|d| {
+                            d.custom_id("verify/submit")
+                                .title("Verify")
+                                .components(|c| {
+                                    c.create_action_row(|row| {
+                                        row.create_input_text(|i| {
+                                            i.custom_id("name")
+                                                .label("Full name")
+                                                .style(InputTextStyle::Short)
+                                                .max_length(100)
+                                                .required(true)
+                                        })
+                                    })
+                                    .create_action_row(
+                                        |row| {
+                                            row.create_input_text(|i| {
+                                                i.custom_id("net_id")
+                                                    .label("Net-id")
+                                                    .placeholder("abc123456")
+                                                    .style(InputTextStyle::Short)
+                                                    .min_length(9)
+                                                    .max_length(9)
+                                                    .required(true)
+                                            })
+                                        },
+                                    )
+                                })
+                        })
+                })
+                .await?;
+            }
+            "verify/code" => {
+                if !self.codes.lock().await.contains_key(&int.user.id) {
+                    return Err(eyre::eyre!(
+                        "You have no code waiting, press Verify to get a new one"
+                    ));
+                }
+                int.create_interaction_response(&ctx.http, |r| {
+                    r.kind(InteractionResponseType::Modal)
+                        .interaction_response_data(|d| {
+                            d.custom_id("verify/confirm")
+                                .title("Enter your code")
+                                .components(|c| {
+                                    c.create_action_row(|row| {
+                                        row.create_input_text(|i| {
+                                            i.custom_id("code")
+                                                .label("The code from your email")
+                                                .style(InputTextStyle::Short)
+                                                .min_length(6)
+                                                .max_length(6)
+                                                .required(true)
+                                        })
+                                    })
+                                })
+                        })
+                })
+                .await?;
+            }
+            _ => return Err(eyre::eyre!("Unable to get action")),
+        }
+        Ok(())
+    }
+
+    /// Handle either verification form
+    /// * `verify/submit` checks the name and net-id and emails a code to the member
+    /// * `verify/confirm` checks the code, links the member's account and gives them the member role
+    /// # Arguments
+    /// * `ctx` - The context of the interaction
+    /// * `int` - The modal interaction
+    /// * `bot` - The bot, used for the database and the lockdown state
+    /// # Returns
+    /// The reply to the member, with a button to enter the code once one was sent
+    /// # Errors
+    /// * If the name, net-id or code is missing or wrong
+    /// * If the net-id is already linked to another account
+    /// * If the email cannot be sent
+    /// * If the profile cannot be saved or the role cannot be given
+    #[instrument(skip(self, ctx, int, bot))]
+    pub async fn handle_modal(
+        &self,
+        ctx: &Context,
+        int: &ModalSubmitInteraction,
+        bot: &Bot,
+    ) -> Result<(String, Option<CreateActionRow>)> {
+        match int.data.custom_id.as_str() {
+            "verify/submit" => {
+                let content = self.send_code(int, bot).await?;
+                let mut row = CreateActionRow::default();
+                row.create_button(|b| {
+                    b.custom_id("verify/code")
+                        .label("Enter code")
+                        .style(ButtonStyle::Primary)
+                });
+                Ok((content, Some(row)))
+            }
+            "verify/confirm" => Ok((self.confirm_code(ctx, int, bot).await?, None)),
+            _ => Err(eyre::eyre!("Unable to get action")),
+        }
+    }
+
+    async fn send_code(&self, int: &ModalSubmitInteraction, bot: &Bot) -> Result<String> {
+        let name =
+            input_value(&int.data.components, "name").ok_or(eyre::eyre!("No name provided"))?;
+        let net_id = input_value(&int.data.components, "net_id")
+            .ok_or(eyre::eyre!("No net-id provided"))?
+            .to_lowercase();
+        if !self.net_id.is_match(&net_id) {
+            return Err(eyre::eyre!(
+                "`{}` is not a valid net-id, it should look like abc123456",
+                net_id
+            ));
+        }
+
+        let mailer = self.mailer.as_ref().ok_or(eyre::eyre!(
+            "Verification is not available right now, please contact staff"
+        ))?;
+
+        let user = int.user.id;
+        self.check_net_id_free(bot, user, &net_id).await?;
+
+        let now = Utc::now();
+        let code = format!("{:06}", Uuid::new_v4().as_u128() % 1_000_000);
+        {
+            let mut codes = self.codes.lock().await;
+            codes.retain(|_, pending| pending.expires_on > now);
+            if let Some(pending) = codes.get(&user) {
+                let wait = pending.sent_on + Duration::seconds(RESEND_COOLDOWN_SECS) - now;
+                if wait > Duration::zero() {
+                    return Err(eyre::eyre!(
+                        "A code was just sent, try again in {} seconds",
+                        wait.num_seconds() + 1
+                    ));
+                }
+            }
+            codes.insert(
+                user,
+                PendingCode {
+                    name,
+                    net_id: net_id.clone(),
+                    code: code.clone(),
+                    sent_on: now,
+                    expires_on: now + self.code_ttl,
+                    attempts: 0,
+                },
+            );
+        }
+
+        let email = format!("{}@{}", net_id, self.email_domain);
+        let body = format!(
+            "Your verification code is {}\n\nIt expires in {} minutes. If you did not ask for it, you can ignore this email.",
+            code,
+            self.code_ttl.num_minutes()
+        );
+        if let Err(why) = mailer
+            .send(&email, "Your Discord verification code", &body)
+            .await
+        {
+            self.codes.lock().await.remove(&user);
+            warn!("Unable to email a code to {}: {:?}", user, why);
+            return Err(eyre::eyre!(
+                "Unable to send the email, please try again later"
+            ));
+        }
+        info!("Sent a verification code to {}", user);
+
+        Ok(format!(
+            "A code was sent to **{}**, press the button below to enter it. It expires in {} minutes.",
+            email,
+            self.code_ttl.num_minutes()
+        ))
+    }
+
+    async fn confirm_code(
+        &self,
+        ctx: &Context,
+        int: &ModalSubmitInteraction,
+        bot: &Bot,
+    ) -> Result<String> {
+        let code =
+            input_value(&int.data.components, "code").ok_or(eyre::eyre!("No code provided"))?;
+        let user = int.user.id;
+
+        let (name, net_id) = {
+            let mut codes = self.codes.lock().await;
+            let pending = codes.get_mut(&user).ok_or(eyre::eyre!(
+                "You have no code waiting, press Verify to get a new one"
+            ))?;
+            if pending.expires_on <= Utc::now() {
+                codes.remove(&user);
+                return Err(eyre::eyre!(
+                    "Your code has expired, press Verify to get a new one"
+                ));
+            }
+            if pending.code != code {
+                pending.attempts += 1;
+                let left = self.max_attempts.saturating_sub(pending.attempts);
+                if left == 0 {
+                    codes.remove(&user);
+                    return Err(eyre::eyre!("Wrong code, press Verify to get a new one"));
+                }
+                return Err(eyre::eyre!("Wrong code, {} attempts left", left));
+            }
+            let pending = codes
+                .remove(&user)
+                .ok_or(eyre::eyre!("Unable to get the code"))?;
+            (pending.name, pending.net_id)
+        };
+
+        // Someone else may have linked the net-id since the code was sent
+        self.check_net_id_free(bot, user, &net_id).await?;
+        // Members verifying during a lockdown are saved so a restart cannot lose them
+        let locked = bot.raid_manager.is_locked().await;
+        bot.firestore_manager
+            .discord_set(&Discord {
+                id: user.to_string(),
+                name: Some(name),
+                net_id: Some(net_id),
+                pending_role: locked,
+            })
+            .await?;
+        info!("Linked {} to their net-id", user);
+
+        if locked {
+            return Ok(
+                "Thanks! The server is locked down right now, you will get access once it is lifted"
+                    .to_string(),
+            );
+        }
+        self.grant(ctx, user).await?;
+        Ok("Thanks! You are now verified".to_string())
+    }
+
+    /// Make sure a net-id is not linked to another account
+    async fn check_net_id_free(&self, bot: &Bot, user: UserId, net_id: &str) -> Result<()> {
+        if let Some(profile) = bot.firestore_manager.discord_by_net_id(net_id).await? {
+            if profile.id != user.to_string() {
+                return Err(eyre::eyre!(
+                    "This net-id is already linked to another account, please contact staff"
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// Give the member role to everyone who verified during a lockdown
+    /// # Returns
+    /// How many members were given the role
+    /// # Errors
+    /// * If the database is unable to get the waiting members
+    #[instrument(skip(self, ctx, bot))]
+    pub async fn grant_pending(&self, ctx: &Context, bot: &Bot) -> Result<usize> {
+        let pending = bot.firestore_manager.discord_pending().await?;
+        let mut granted = 0;
+        for mut profile in pending {
+            let user = UserId(profile.id.parse::<u64>()?);
+            if let Err(why) = self.grant(ctx, user).await {
+                warn!("Unable to give {} the member role: {:?}", user, why);
+                continue;
+            }
+            granted += 1;
+            profile.pending_role = false;
+            if let Err(why) = bot.firestore_manager.discord_set(&profile).await {
+                warn!("Unable to save that {} was let in: {:?}", user, why);
+            }
+        }
+        Ok(granted)
+    }
+
+    async fn grant(&self, ctx: &Context, user: UserId) -> Result<()> {
+        let member_role = self
+            .member_role
+            .ok_or(eyre::eyre!("The member role is not configured"))?;
+        ctx.http
+            .add_member_role(self.guild_id.0, user.0, member_role.0, Some("Verified"))
+            .await?;
+        Ok(())
+    }
+}
+
+/// Whether a message is the verification panel
+fn is_panel(msg: &Message) -> bool {
+    msg.components
+        .iter()
+        .flat_map(|row| row.components.iter())
+        .any(|component| {
+            matches!(component, ActionRowComponent::Button(button)
+                if button.custom_id.as_deref() == Some(VERIFY_BUTTON))
+        })
+}
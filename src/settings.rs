@@ -27,6 +27,18 @@ pub struct Settings {
     pub circles: Circles,
     pub channels: Channels,
     pub roles: Roles,
+    #[serde(default)]
+    pub reports: Reports,
+    #[serde(default)]
+    pub moderation: Moderation,
+    #[serde(default)]
+    pub automod: Automod,
+    #[serde(default)]
+    pub raid: Raid,
+    #[serde(default)]
+    pub verification: Verification,
+    #[serde(default)]
+    pub role_panel: RolePanel,
 }
 
 #[derive(Default, Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
@@ -84,6 +96,13 @@ pub struct Channels {
     pub roles: String,
     #[serde(rename = "mod")]
     pub mod_field: String,
+    /// Where appeals are sent for directors to decide on
+    #[serde(default)]
+    pub appeals: String,
+    /// Where edited and deleted messages are logged, needs the message content intent
+    /// enabled in the developer portal
+    #[serde(default)]
+    pub audit: String,
 }
 
 #[derive(Default, Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
@@ -104,6 +123,323 @@ pub struct Divisions {
     pub hackutd: String,
 }
 
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct Reports {
+    /// How long a report can wait for a category before it is dropped,
+    /// interaction tokens only last 15 minutes so keep this below 900
+    pub pending_ttl_secs: u64,
+    /// How often abandoned reports are swept
+    pub sweep_interval_secs: u64,
+    /// How many people need to report the same message before staff are pinged
+    pub escalation_threshold: usize,
+    /// Secret key reporters are hashed with, reports are turned off until it is set.
+    /// It must never be stored in the database, with both anyone could name the reporters
+    pub reporter_key: String,
+    /// How many reports one person can send per hour
+    pub max_per_hour: usize,
+    /// How long before someone can report the same person again, at most a week
+    pub target_cooldown_secs: u64,
+}
+
+impl Default for Reports {
+    fn default() -> Self {
+        Self {
+            pending_ttl_secs: 600,
+            sweep_interval_secs: 60,
+            escalation_threshold: 3,
+            reporter_key: String::new(),
+            max_per_hour: 5,
+            target_cooldown_secs: 600,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct Moderation {
+    /// What happens to a member once they reach a number of strikes,
+    /// members past the last step keep getting its action
+    pub ladder: Vec<LadderStep>,
+    /// One strike is forgiven for every this many days without a new one, 0 turns decay off
+    pub strike_decay_days: i64,
+    /// How often every member is checked for strikes to forgive
+    pub decay_interval_secs: u64,
+}
+
+impl Default for Moderation {
+    fn default() -> Self {
+        Self {
+            // Nothing escalates on its own until a ladder is configured
+            ladder: Vec::new(),
+            strike_decay_days: 30,
+            decay_interval_secs: 3600,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LadderStep {
+    pub strikes: i64,
+    pub action: LadderAction,
+    /// How long a mute lasts, at most a year, mutes without a duration last until lifted by staff
+    #[serde(default)]
+    pub duration_hours: Option<i64>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum LadderAction {
+    Mute,
+    Kick,
+    Ban,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct Automod {
+    /// Off until configured, needs the message content intent enabled in the developer portal
+    pub enabled: bool,
+    /// Members with any of these roles are never checked, staff are always exempt
+    pub exempt_roles: Vec<String>,
+    /// Checked in order, only the first rule a message breaks is applied
+    pub rules: Vec<AutomodRule>,
+}
+
+impl Default for Automod {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            exempt_roles: Vec::new(),
+            rules: vec![
+                AutomodRule::new(AutomodCheck::InviteLinks, AutomodAction::Delete),
+                AutomodRule::new(AutomodCheck::MassMentions { max: 5 }, AutomodAction::Mute),
+                AutomodRule::new(
+                    AutomodCheck::Repeated {
+                        count: 4,
+                        window_secs: 60,
+                    },
+                    AutomodAction::Delete,
+                ),
+                AutomodRule::new(
+                    AutomodCheck::Caps {
+                        min_length: 20,
+                        max_percent: 70,
+                    },
+                    AutomodAction::Warn,
+                ),
+            ],
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AutomodRule {
+    pub check: AutomodCheck,
+    pub action: AutomodAction,
+    /// The channels the rule applies to, every channel when empty
+    #[serde(default)]
+    pub channels: Vec<String>,
+    /// Channels the rule never applies to
+    #[serde(default)]
+    pub ignored_channels: Vec<String>,
+    /// How long the mute action lasts
+    #[serde(default = "default_automod_mute")]
+    pub mute_minutes: i64,
+}
+
+impl AutomodRule {
+    fn new(check: AutomodCheck, action: AutomodAction) -> Self {
+        Self {
+            check,
+            action,
+            channels: Vec::new(),
+            ignored_channels: Vec::new(),
+            mute_minutes: default_automod_mute(),
+        }
+    }
+}
+
+fn default_automod_mute() -> i64 {
+    10
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum AutomodCheck {
+    /// Any of the words, ignoring case
+    #[serde(rename_all = "camelCase")]
+    BannedWords { words: Vec<String> },
+    /// Any of the regular expressions
+    #[serde(rename_all = "camelCase")]
+    Regex { patterns: Vec<String> },
+    /// Discord server invites
+    InviteLinks,
+    /// More than `max` users and roles mentioned, or an everyone mention
+    #[serde(rename_all = "camelCase")]
+    MassMentions { max: usize },
+    /// Messages of at least `min_length` letters with more than `max_percent` of them in caps
+    #[serde(rename_all = "camelCase")]
+    Caps {
+        min_length: usize,
+        max_percent: usize,
+    },
+    /// The same message sent `count` times within `window_secs`, at most an hour
+    #[serde(rename_all = "camelCase")]
+    Repeated { count: usize, window_secs: i64 },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum AutomodAction {
+    /// Delete the message
+    Delete,
+    /// Delete the message and tell the member why
+    Warn,
+    /// Delete the message and strike the member
+    Strike,
+    /// Delete the message and mute the member for `mute_minutes`
+    Mute,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct Raid {
+    /// Off until configured for the server
+    pub enabled: bool,
+    /// Joins within `window_secs` that count as a raid, 0 turns this check off
+    pub max_joins: usize,
+    /// Accounts younger than `new_account_days` joining within `window_secs` that count as a raid,
+    /// 0 turns this check off
+    pub max_new_accounts: usize,
+    /// At most 365
+    pub new_account_days: i64,
+    /// At most a day
+    pub window_secs: i64,
+    /// Whether a detected raid locks the server down without waiting for staff
+    pub auto_lockdown: bool,
+}
+
+impl Default for Raid {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            max_joins: 10,
+            max_new_accounts: 5,
+            new_account_days: 7,
+            window_secs: 60,
+            auto_lockdown: false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct Verification {
+    /// Codes are sent to `<net-id>@<email_domain>`
+    pub email_domain: String,
+    /// How long a code is valid for, at most a day
+    pub code_minutes: i64,
+    /// Wrong codes allowed before the member has to ask for a new one
+    pub max_attempts: u32,
+    /// No codes are sent until a transport is set
+    pub mail: Option<Mail>,
+}
+
+impl Default for Verification {
+    fn default() -> Self {
+        Self {
+            email_domain: "utdallas.edu".to_string(),
+            code_minutes: 10,
+            max_attempts: 5,
+            mail: None,
+        }
+    }
+}
+
+/// How verification emails are sent
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum Mail {
+    /// Send through an SMTP relay using STARTTLS
+    #[serde(rename_all = "camelCase")]
+    Smtp {
+        host: String,
+        #[serde(default = "default_smtp_port")]
+        port: u16,
+        username: String,
+        password: String,
+        from: String,
+    },
+    /// Write emails to the log, and to `path` if set, for local testing
+    #[serde(rename_all = "camelCase")]
+    Log {
+        #[serde(default)]
+        path: Option<String>,
+    },
+}
+
+fn default_smtp_port() -> u16 {
+    587
+}
+
+/// The roles members can give themselves from the panel in the roles channel
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct RolePanel {
+    pub title: String,
+    pub description: String,
+    /// The roles on the panel, the division roles are used when this is empty
+    pub roles: Vec<RoleOption>,
+}
+
+impl Default for RolePanel {
+    fn default() -> Self {
+        Self {
+            title: "Divisions".to_string(),
+            description: "Pick the divisions you want to hear from, press a button again to leave"
+                .to_string(),
+            roles: Vec::new(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RoleOption {
+    /// The id of the role
+    pub role: String,
+    pub label: String,
+    #[serde(default)]
+    pub emoji: Option<String>,
+}
+
+impl RoleOption {
+    fn new(role: &str, label: &str) -> Self {
+        Self {
+            role: role.to_string(),
+            label: label.to_string(),
+            emoji: None,
+        }
+    }
+}
+
+impl RolePanel {
+    /// The roles on the panel, falling back to the division roles
+    pub fn options(&self, divisions: &Divisions) -> Vec<RoleOption> {
+        if !self.roles.is_empty() {
+            return self.roles.clone();
+        }
+        vec![
+            RoleOption::new(&divisions.projects, "Projects"),
+            RoleOption::new(&divisions.education, "Education"),
+            RoleOption::new(&divisions.hackutd, "HackUTD"),
+        ]
+    }
+}
+
 impl Settings {
     pub fn new() -> Self {
         debug!("Loading settings.json...");
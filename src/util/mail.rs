@@ -0,0 +1,89 @@
+use std::sync::Arc;
+
+use color_eyre::Result;
+use lettre::{
+    message::Mailbox, transport::smtp::authentication::Credentials, AsyncSmtpTransport,
+    AsyncTransport, Message, Tokio1Executor,
+};
+use serenity::async_trait;
+use tokio::io::AsyncWriteExt;
+use tracing::info;
+
+use crate::settings::Mail;
+
+/// Something that can deliver an email
+#[async_trait]
+pub trait MailTransport: Send + Sync {
+    /// Send a plain text email
+    /// # Arguments
+    /// * `to` - The address of the recipient
+    /// * `subject` - The subject of the email
+    /// * `body` - The text of the email
+    /// # Errors
+    /// * If the email cannot be delivered
+    async fn send(&self, to: &str, subject: &str, body: &str) -> Result<()>;
+}
+
+/// Build the transport configured in the settings
+/// # Errors
+/// * If the SMTP settings are invalid
+pub fn transport(mail: &Mail) -> Result<Arc<dyn MailTransport>> {
+    let transport: Arc<dyn MailTransport> = match mail {
+        Mail::Smtp {
+            host,
+            port,
+            username,
+            password,
+            from,
+        } => Arc::new(SmtpMailer {
+            mailer: AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(host)?
+                .port(*port)
+                .credentials(Credentials::new(username.clone(), password.clone()))
+                .build(),
+            from: from.parse()?,
+        }),
+        Mail::Log { path } => Arc::new(LogMailer { path: path.clone() }),
+    };
+    Ok(transport)
+}
+
+/// Sends emails through an SMTP relay
+pub struct SmtpMailer {
+    mailer: AsyncSmtpTransport<Tokio1Executor>,
+    from: Mailbox,
+}
+
+#[async_trait]
+impl MailTransport for SmtpMailer {
+    async fn send(&self, to: &str, subject: &str, body: &str) -> Result<()> {
+        let email = Message::builder()
+            .from(self.from.clone())
+            .to(to.parse()?)
+            .subject(subject)
+            .body(body.to_string())?;
+        self.mailer.send(email).await?;
+        Ok(())
+    }
+}
+
+/// Writes emails to the log instead of sending them, for local testing
+pub struct LogMailer {
+    path: Option<String>,
+}
+
+#[async_trait]
+impl MailTransport for LogMailer {
+    async fn send(&self, to: &str, subject: &str, body: &str) -> Result<()> {
+        info!("Email to {}: {}\n{}", to, subject, body);
+        if let Some(path) = &self.path {
+            let mut file = tokio::fs::OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(path)
+                .await?;
+            let email = format!("To: {to}\nSubject: {subject}\n\n{body}\n\n");
+            file.write_all(email.as_bytes()).await?;
+        }
+        Ok(())
+    }
+}
@@ -1 +1,6 @@
+pub mod mail;
+pub mod modal;
 mod response;
+pub mod roles;
+pub mod text;
+pub mod time;
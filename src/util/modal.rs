@@ -0,0 +1,20 @@
+use serenity::model::prelude::component::{ActionRow, ActionRowComponent};
+
+/// Get the value of a text input from a submitted modal
+/// # Arguments
+/// * `components` - The action rows of the modal submission
+/// * `custom_id` - The custom id of the text input
+/// # Returns
+/// * `None` if the input is missing or was left empty
+pub fn input_value(components: &[ActionRow], custom_id: &str) -> Option<String> {
+    components
+        .iter()
+        .flat_map(|row| row.components.iter())
+        .find_map(|component| match component {
+            ActionRowComponent::InputText(input) if input.custom_id == custom_id => {
+                Some(input.value.trim().to_string())
+            }
+            _ => None,
+        })
+        .filter(|value| !value.is_empty())
+}
@@ -0,0 +1,12 @@
+use serenity::model::prelude::{Member, RoleId};
+
+/// Check whether a guild member holds the role with the given id
+/// # Arguments
+/// * `member` - The member to check, if the interaction happened in a guild
+/// * `role` - The id of the role, as found in the settings
+pub fn has_role(member: Option<&Member>, role: &str) -> bool {
+    let Ok(role) = role.parse::<u64>() else {
+        return false;
+    };
+    matches!(member, Some(m) if m.roles.contains(&RoleId(role)))
+}
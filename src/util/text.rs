@@ -0,0 +1,8 @@
+/// Shorten text to at most `max` characters so it fits in an embed
+pub fn truncate(text: &str, max: usize) -> String {
+    match text.char_indices().nth(max) {
+        Some((idx, _)) => format!("{}...", &text[..idx]),
+        None if text.is_empty() => "*No text*".to_string(),
+        None => text.to_string(),
+    }
+}
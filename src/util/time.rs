@@ -0,0 +1,54 @@
+use chrono::Duration;
+
+/// The longest duration accepted for a punishment, anything longer should be permanent
+pub const MAX_DURATION_MINUTES: i64 = 60 * 24 * 365;
+
+/// Build a duration from a number of minutes, `None` when it is not positive or
+/// longer than [`MAX_DURATION_MINUTES`]
+pub fn bounded_minutes(minutes: i64) -> Option<Duration> {
+    if minutes <= 0 || minutes > MAX_DURATION_MINUTES {
+        return None;
+    }
+    Some(Duration::minutes(minutes))
+}
+
+/// Format a duration the way people write it, e.g. `2d 4h` or `35m`
+pub fn format_duration(duration: Duration) -> String {
+    match (
+        duration.num_days(),
+        duration.num_hours() % 24,
+        duration.num_minutes() % 60,
+    ) {
+        (0, 0, minutes) => format!("{minutes}m"),
+        (0, hours, minutes) => format!("{hours}h {minutes}m"),
+        (days, hours, _) => format!("{days}d {hours}h"),
+    }
+}
+
+/// Parse a duration written like `30m`, `12h` or `1d12h`, units are `m`, `h`, `d` and `w`.
+/// Durations longer than a year are rejected
+pub fn parse_duration(input: &str) -> Option<Duration> {
+    let mut minutes: i64 = 0;
+    let mut number = String::new();
+    for c in input.chars().filter(|c| !c.is_whitespace()) {
+        if c.is_ascii_digit() {
+            number.push(c);
+            continue;
+        }
+        let amount: i64 = number.parse().ok()?;
+        number.clear();
+        let unit = match c.to_ascii_lowercase() {
+            'm' => 1,
+            'h' => 60,
+            'd' => 60 * 24,
+            'w' => 60 * 24 * 7,
+            _ => return None,
+        };
+        minutes = minutes.checked_add(amount.checked_mul(unit)?)?;
+    }
+    // A trailing number without a unit is ambiguous
+    if !number.is_empty() {
+        return None;
+    }
+    bounded_minutes(minutes)
+}